@@ -1,4 +1,4 @@
-use rand::{Rng, RngCore};
+use rand::{seq::index, Rng, RngCore};
 
 use crate::*;
 
@@ -44,6 +44,290 @@ impl CrossoverMethod for UniformCrossover {
     }
 }
 
+/// Cuts both parents at the same `points` random places and glues the
+/// pieces together, alternating between the parents - so genes that sit
+/// next to each other (e.g. weights of the same neuron) tend to be
+/// inherited together.
+#[derive(Clone, Debug)]
+pub struct KPointCrossover {
+    points: usize,
+}
+
+impl KPointCrossover {
+    /// Chromosomes shorter than `points + 1` genes get cut in between
+    /// each gene.
+    pub fn new(points: usize) -> Self {
+        assert!(points > 0);
+
+        Self { points }
+    }
+
+    pub fn single_point() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Default for KPointCrossover {
+    fn default() -> Self {
+        Self::single_point()
+    }
+}
+
+impl CrossoverMethod for KPointCrossover {
+    fn crossover(
+        &self,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+        rng: &mut dyn RngCore,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        let cuts = parent_a.len().saturating_sub(1);
+
+        // Cutting at `n` means that gene `n` is the first one of a piece
+        let mut points: Vec<_> = index::sample(rng, cuts, self.points.min(cuts))
+            .into_iter()
+            .map(|point| point + 1)
+            .collect();
+
+        points.sort_unstable();
+
+        let mut points = points.into_iter().peekable();
+        let mut from_a = true;
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .enumerate()
+            .map(|(n, (&a, &b))| {
+                if points.next_if_eq(&n).is_some() {
+                    from_a = !from_a;
+                }
+
+                if from_a {
+                    a
+                } else {
+                    b
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whole-arithmetic crossover: every gene of the child is the same
+/// weighted average of the parents' genes.
+#[derive(Clone, Debug)]
+pub struct ArithmeticCrossover {
+    /// Weight of the first parent:
+    /// - 0.5 = child is exactly in between the parents
+    /// - 1.0 = child is a copy of the first parent
+    weight: f32,
+}
+
+impl ArithmeticCrossover {
+    pub fn new(weight: f32) -> Self {
+        assert!((0.0..=1.0).contains(&weight));
+
+        Self { weight }
+    }
+}
+
+impl Default for ArithmeticCrossover {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl CrossoverMethod for ArithmeticCrossover {
+    fn crossover(
+        &self,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+        _rng: &mut dyn RngCore,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .map(|(&a, &b)| self.weight * a + (1.0 - self.weight) * b)
+            .collect()
+    }
+}
+
+/// BLX-α: each gene of the child is drawn uniformly from the range
+/// spanned by the parents' genes, extended by `alpha` times its length
+/// on both sides - so that the population doesn't shrink towards its
+/// center with each generation.
+#[derive(Clone, Debug)]
+pub struct BlendCrossover {
+    /// - 0.0 = genes stay in between the parents' ones
+    /// - 0.5 = genes land outside of that range half of the time
+    alpha: f32,
+}
+
+impl BlendCrossover {
+    pub fn new(alpha: f32) -> Self {
+        assert!(alpha >= 0.0);
+
+        Self { alpha }
+    }
+}
+
+impl Default for BlendCrossover {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl CrossoverMethod for BlendCrossover {
+    fn crossover(
+        &self,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+        rng: &mut dyn RngCore,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .map(|(&a, &b)| {
+                let extension = self.alpha * (a - b).abs();
+
+                rng.gen_range((a.min(b) - extension)..=(a.max(b) + extension))
+            })
+            .collect()
+    }
+}
+
+/// Simulated binary crossover (SBX): mimics what single-point crossover
+/// does to binary-encoded numbers - each gene of the child lands close
+/// to one of the parents' genes, with the spread proportional to how far
+/// apart the parents are.
+#[derive(Clone, Debug)]
+pub struct SimulatedBinaryCrossover {
+    /// Distribution index:
+    /// - small (e.g. 2.0) = children can land far from their parents
+    /// - big (e.g. 20.0) = children stay close to their parents
+    eta: f32,
+}
+
+impl SimulatedBinaryCrossover {
+    pub fn new(eta: f32) -> Self {
+        assert!(eta >= 0.0);
+
+        Self { eta }
+    }
+}
+
+impl Default for SimulatedBinaryCrossover {
+    fn default() -> Self {
+        Self::new(2.0)
+    }
+}
+
+impl CrossoverMethod for SimulatedBinaryCrossover {
+    fn crossover(
+        &self,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+        rng: &mut dyn RngCore,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .map(|(&a, &b)| {
+                let u: f32 = rng.gen();
+
+                let beta = if u <= 0.5 {
+                    (2.0 * u).powf(1.0 / (self.eta + 1.0))
+                } else {
+                    (1.0 / (2.0 * (1.0 - u))).powf(1.0 / (self.eta + 1.0))
+                };
+
+                // SBX creates two children, symmetric around the parents'
+                // mean - we pick one of them at random
+                let (near, far) = if rng.gen_bool(0.5) { (a, b) } else { (b, a) };
+
+                0.5 * ((1.0 + beta) * near + (1.0 - beta) * far)
+            })
+            .collect()
+    }
+}
+
+/// Crossover of [`Genome`]s, as described in the NEAT paper:
+///
+/// - genes present in both parents (matching innovation numbers) are
+///   inherited randomly from either of them,
+/// - genes present in only one parent (disjoint & excess) are inherited
+///   from the fitter one, since that's the structure that's been proven
+///   to work.
+#[derive(Clone, Debug)]
+pub struct NeatCrossover {
+    /// Probability of a matching gene staying disabled when it's been
+    /// disabled in either of the parents.
+    disabled_chance: f32,
+}
+
+impl NeatCrossover {
+    pub fn new(disabled_chance: f32) -> Self {
+        assert!((0.0..=1.0).contains(&disabled_chance));
+
+        Self { disabled_chance }
+    }
+
+    pub fn crossover(&self, fitter: &Genome, other: &Genome, rng: &mut dyn RngCore) -> Genome {
+        let nodes = fitter
+            .nodes()
+            .iter()
+            .map(|node| match other.node(node.id) {
+                Some(other) if rng.gen_bool(0.5) => other.clone(),
+                _ => node.clone(),
+            })
+            .collect();
+
+        let connections = fitter
+            .connections()
+            .iter()
+            .map(|conn| {
+                let matching = other
+                    .connections()
+                    .iter()
+                    .find(|other| other.innovation == conn.innovation);
+
+                let Some(matching) = matching else {
+                    return conn.clone();
+                };
+
+                let mut child = if rng.gen_bool(0.5) {
+                    conn.clone()
+                } else {
+                    matching.clone()
+                };
+
+                child.enabled = if conn.enabled && matching.enabled {
+                    true
+                } else {
+                    !rng.gen_bool(self.disabled_chance as _)
+                };
+
+                child
+            })
+            .collect();
+
+        Genome::new(nodes, connections)
+    }
+}
+
+impl Default for NeatCrossover {
+    fn default() -> Self {
+        Self::new(0.75)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +348,233 @@ mod tests {
         assert_eq!(diff_a, 49);
         assert_eq!(diff_b, 51);
     }
+
+    fn parents() -> (Chromosome, Chromosome) {
+        let parent_a = (1..=100).map(|n| n as f32).collect();
+        let parent_b = (1..=100).map(|n| -n as f32).collect();
+
+        (parent_a, parent_b)
+    }
+
+    /// Returns how many times the child switches between the parents.
+    fn switches(child: &Chromosome) -> usize {
+        let genes: Vec<_> = child.iter().collect();
+
+        genes
+            .windows(2)
+            .filter(|genes| genes[0].signum() != genes[1].signum())
+            .count()
+    }
+
+    #[test]
+    fn single_point_crossover() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+
+        let child = KPointCrossover::single_point().crossover(&parent_a, &parent_b, &mut rng);
+
+        let diff_a = child.iter().zip(parent_a).filter(|(c, p)| *c != p).count();
+        let diff_b = child.iter().zip(parent_b).filter(|(c, p)| *c != p).count();
+
+        assert_eq!(diff_a, 81);
+        assert_eq!(diff_b, 19);
+        assert_eq!(switches(&child), 1);
+        assert_eq!(child[0], 1.0);
+    }
+
+    #[test]
+    fn multi_point_crossover() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+
+        let child = KPointCrossover::new(4).crossover(&parent_a, &parent_b, &mut rng);
+
+        let diff_a = child.iter().zip(parent_a).filter(|(c, p)| *c != p).count();
+        let diff_b = child.iter().zip(parent_b).filter(|(c, p)| *c != p).count();
+
+        assert_eq!(diff_a, 59);
+        assert_eq!(diff_b, 41);
+        assert_eq!(switches(&child), 4);
+    }
+
+    #[test]
+    fn k_point_crossover_of_short_chromosomes() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let parent_a: Chromosome = vec![1.0, 2.0, 3.0].into_iter().collect();
+        let parent_b: Chromosome = vec![-1.0, -2.0, -3.0].into_iter().collect();
+
+        let child = KPointCrossover::new(10).crossover(&parent_a, &parent_b, &mut rng);
+
+        assert_eq!(child, vec![1.0, -2.0, 3.0].into_iter().collect());
+    }
+
+    #[test]
+    fn arithmetic_crossover() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+
+        let child = ArithmeticCrossover::new(0.25).crossover(&parent_a, &parent_b, &mut rng);
+        let expected: Chromosome = (1..=100).map(|n| -0.5 * n as f32).collect();
+
+        assert_eq!(child, expected);
+    }
+
+    #[test]
+    fn blend_crossover() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+
+        let child = BlendCrossover::default().crossover(&parent_a, &parent_b, &mut rng);
+
+        let outside = child
+            .iter()
+            .zip(parent_a.iter())
+            .filter(|(c, p)| c.abs() > **p)
+            .count();
+
+        let out_of_bounds = child
+            .iter()
+            .zip(parent_a.iter())
+            .filter(|(c, p)| c.abs() > 2.0 * **p)
+            .count();
+
+        assert_eq!(outside, 40);
+        assert_eq!(out_of_bounds, 0);
+    }
+
+    #[test]
+    fn simulated_binary_crossover() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+
+        let child = SimulatedBinaryCrossover::default().crossover(&parent_a, &parent_b, &mut rng);
+
+        let closer_to_a = child
+            .iter()
+            .zip(parent_a.iter())
+            .filter(|(c, _)| **c > 0.0)
+            .count();
+
+        // Genes within 10% of the parents' distance from either of them
+        let close_to_parent = child
+            .iter()
+            .zip(parent_a.iter())
+            .filter(|(c, p)| (c.abs() - **p).abs() < 0.2 * **p)
+            .count();
+
+        assert_eq!(closer_to_a, 50);
+        assert_eq!(close_to_parent, 51);
+    }
+
+    #[test]
+    fn simulated_binary_crossover_with_big_eta_stays_close_to_parents() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+
+        let child = SimulatedBinaryCrossover::new(1000.0).crossover(&parent_a, &parent_b, &mut rng);
+
+        for (c, p) in child.iter().zip(parent_a.iter()) {
+            approx::assert_relative_eq!(c.abs(), *p, max_relative = 0.01);
+        }
+    }
+
+    mod neat_crossover {
+        use super::*;
+
+        fn connection(innovation: usize, weight: f32, enabled: bool) -> ConnectionGene {
+            ConnectionGene {
+                innovation,
+                from: innovation,
+                to: 100,
+                weight,
+                enabled,
+            }
+        }
+
+        fn node(id: usize, kind: NodeKind) -> NodeGene {
+            NodeGene {
+                id,
+                kind,
+                bias: 0.0,
+            }
+        }
+
+        fn genome(connections: Vec<ConnectionGene>) -> Genome {
+            let mut nodes: Vec<_> = connections
+                .iter()
+                .map(|conn| node(conn.from, NodeKind::Input))
+                .collect();
+
+            nodes.push(node(100, NodeKind::Output));
+
+            Genome::new(nodes, connections)
+        }
+
+        #[test]
+        fn inherits_structure_from_fitter_parent() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+            let fitter = genome(vec![
+                connection(0, 1.0, true),
+                connection(1, 1.0, true),
+                connection(3, 1.0, true),
+                connection(5, 1.0, true),
+            ]);
+
+            let other = genome(vec![
+                connection(0, -1.0, true),
+                connection(1, -1.0, true),
+                connection(2, -1.0, true),
+                connection(4, -1.0, true),
+            ]);
+
+            let child = NeatCrossover::default().crossover(&fitter, &other, &mut rng);
+
+            let innovations: Vec<_> = child
+                .connections()
+                .iter()
+                .map(|conn| conn.innovation)
+                .collect();
+
+            assert_eq!(innovations, vec![0, 1, 3, 5]);
+
+            // Disjoint & excess genes always come from the fitter parent
+            assert_eq!(child.connections()[2].weight, 1.0);
+            assert_eq!(child.connections()[3].weight, 1.0);
+        }
+
+        #[test]
+        fn picks_matching_genes_from_both_parents() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let fitter = genome((0..100).map(|n| connection(n, 1.0, true)).collect());
+            let other = genome((0..100).map(|n| connection(n, -1.0, true)).collect());
+
+            let child = NeatCrossover::default().crossover(&fitter, &other, &mut rng);
+
+            let from_fitter = child
+                .connections()
+                .iter()
+                .filter(|conn| conn.weight > 0.0)
+                .count();
+
+            assert_eq!(from_fitter, 54);
+        }
+
+        #[test]
+        fn keeps_genes_disabled_in_either_parent_mostly_disabled() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let fitter = genome((0..100).map(|n| connection(n, 1.0, true)).collect());
+            let other = genome((0..100).map(|n| connection(n, -1.0, false)).collect());
+
+            let child = NeatCrossover::default().crossover(&fitter, &other, &mut rng);
+
+            let disabled = child
+                .connections()
+                .iter()
+                .filter(|conn| !conn.enabled)
+                .count();
+
+            assert_eq!(disabled, 84);
+        }
+    }
 }
@@ -6,6 +6,40 @@ pub struct GeneticAlgorithm<S, C, M> {
     selection_method: S,
     crossover_method: C,
     mutation_method: M,
+    elitism: Elitism,
+    fitness_scaling: FitnessScaling,
+}
+
+/// How many of the fittest individuals get carried over into the next
+/// generation unchanged, so that the best solution found so far can't
+/// get lost to an unlucky crossover or mutation.
+///
+/// Individuals of equal fitness are ordered by their position in the
+/// population, so that it's always the same ones that survive.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Elitism {
+    #[default]
+    None,
+
+    /// Keeps given number of individuals (or the whole population, if
+    /// it's smaller)
+    Count(usize),
+
+    /// Keeps given fraction of the population (`0.0..=1.0`), rounded
+    /// down
+    Fraction(f32),
+}
+
+impl Elitism {
+    /// Returns how many individuals to keep out of a population of given
+    /// size.
+    pub fn count(self, population: usize) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Count(count) => count.min(population),
+            Self::Fraction(fraction) => ((population as f32) * fraction).floor() as usize,
+        }
+    }
 }
 
 impl<S, C, M> GeneticAlgorithm<S, C, M>
@@ -19,26 +53,36 @@ where
         I: Individual,
     {
         assert!(!population.is_empty());
-        let new_population = (0..population.len())
-            .map(|_| {
-                let parent_a = self
-                    .selection_method
-                    .select(population, rng)
-                    .to_chromosome();
-                let parent_b = self
-                    .selection_method
-                    .select(population, rng)
-                    .to_chromosome();
+
+        let elites = elites(population, self.elitism.count(population.len()));
+        let fitness: Vec<_> = population.iter().map(Individual::fitness).collect();
+        let fitness = self.fitness_scaling.scale(&fitness);
+
+        // Two parents per child
+        let parents = self.selection_method.select_indices(
+            &fitness,
+            2 * (population.len() - elites.len()),
+            rng,
+        );
+
+        let new_population = elites
+            .iter()
+            .map(|&idx| I::from_chromosome(population[idx].to_chromosome().clone()))
+            .chain(parents.chunks_exact(2).map(|parents| {
+                let parent_a = population[parents[0]].to_chromosome();
+                let parent_b = population[parents[1]].to_chromosome();
 
                 let mut child = self.crossover_method.crossover(parent_a, parent_b, rng);
 
                 self.mutation_method.mutate(&mut child, rng);
 
                 I::from_chromosome(child)
-            })
+            }))
             .collect();
 
-        let stats = Statistics::new(population);
+        self.mutation_method.next_generation();
+
+        let stats = Statistics::new(population, elites);
 
         (new_population, stats)
     }
@@ -48,8 +92,47 @@ where
             selection_method,
             crossover_method,
             mutation_method,
+            elitism: Elitism::None,
+            fitness_scaling: FitnessScaling::None,
         }
     }
+
+    pub fn mutation_method(&self) -> &M {
+        &self.mutation_method
+    }
+
+    pub fn with_elitism(mut self, elitism: Elitism) -> Self {
+        if let Elitism::Fraction(fraction) = elitism {
+            assert!((0.0..=1.0).contains(&fraction));
+        }
+
+        self.elitism = elitism;
+        self
+    }
+
+    pub fn with_fitness_scaling(mut self, fitness_scaling: FitnessScaling) -> Self {
+        self.fitness_scaling = fitness_scaling;
+        self
+    }
+}
+
+/// Returns indices of the `count` fittest individuals, fittest first.
+///
+/// NaN fitness counts as the least fit one.
+fn elites<I>(population: &[I], count: usize) -> Vec<usize>
+where
+    I: Individual,
+{
+    let mut ranked: Vec<_> = (0..population.len()).collect();
+
+    // Stable, so ties go to whoever comes first in the population
+    ranked.sort_by(|&a, &b| {
+        let (a, b) = (population[a].fitness(), population[b].fitness());
+
+        a.is_nan().cmp(&b.is_nan()).then(b.total_cmp(&a))
+    });
+    ranked.truncate(count);
+    ranked
 }
 
 #[cfg(test)]
@@ -95,16 +178,217 @@ mod tests {
         // that'd change is the *magnitude* of difference between
         // initial and output population.
         for _ in 0..10 {
-            population = ga.evolve(&population, &mut rng);
+            (population, _) = ga.evolve(&population, &mut rng);
         }
 
         let expected_population = vec![
-            create_individual(&[0.447_694_9, 2.0648358, 4.3058133]),
-            create_individual(&[1.212_686_7, 1.5538777, 2.886_911]),
-            create_individual(&[1.061_767_8, 2.265_739, 4.428_764]),
-            create_individual(&[0.95909685, 2.4618788, 4.024_733]),
+            create_individual(&[1.0634874, 0.17297453, 3.782661]),
+            create_individual(&[1.1567537, 0.9164492, 1.7261596]),
+            create_individual(&[1.0634874, 3.6249104, 1.9728677]),
+            create_individual(&[1.0634874, 3.6249104, 1.7499502]),
         ];
 
         assert_eq!(population, expected_population);
     }
+
+    #[test]
+    fn advances_mutation_schedule() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let mut ga = GeneticAlgorithm::new(
+            RouletteWheelSelection::new(),
+            UniformCrossover::new(),
+            ScheduledMutation::new(Schedule::Constant(0.5), Schedule::Constant(0.5)),
+        );
+
+        let population = vec![
+            create_individual(&[1.0, 1.0, 1.0]),
+            create_individual(&[1.0, 2.0, 1.0]),
+        ];
+
+        ga.evolve(&population, &mut rng);
+        ga.evolve(&population, &mut rng);
+
+        assert_eq!(ga.mutation_method().generation(), 2);
+    }
+
+    #[test]
+    fn survives_degenerate_populations() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let scalings = [
+            FitnessScaling::None,
+            FitnessScaling::Linear { multiplier: 2.0 },
+            FitnessScaling::SigmaTruncation { c: 1.0 },
+            FitnessScaling::Windowing,
+            FitnessScaling::PowerLaw { exponent: 2.0 },
+        ];
+
+        for scaling in scalings {
+            let mut ga = GeneticAlgorithm::new(
+                RouletteWheelSelection::new(),
+                UniformCrossover::new(),
+                GaussianMutation::new(0.5, 0.5),
+            )
+            .with_fitness_scaling(scaling);
+
+            let zero = vec![create_individual(&[0.0, 0.0]); 4];
+            let negative = vec![create_individual(&[-1.0, -2.0]); 4];
+
+            assert_eq!(ga.evolve(&zero, &mut rng).0.len(), 4);
+            assert_eq!(ga.evolve(&negative, &mut rng).0.len(), 4);
+        }
+    }
+
+    #[test]
+    fn prefers_fittest_of_negative_population() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let mut ga = GeneticAlgorithm::new(
+            TournamentSelection::new(4),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.0, 0.0),
+        );
+
+        let population: Vec<_> = (1..=20)
+            .map(|n| create_individual(&[-n as f32, -n as f32]))
+            .collect();
+
+        let (children, stats) = ga.evolve(&population, &mut rng);
+
+        let avg_fitness =
+            children.iter().map(|child| child.fitness()).sum::<f32>() / children.len() as f32;
+
+        // If negative fitness got flattened, tournaments would pick parents
+        // at random, leaving children about as fit as their parents
+        assert_eq!(stats.avg_fitness(), -21.0);
+        assert!(avg_fitness > -10.0, "{}", avg_fitness);
+    }
+
+    #[test]
+    fn selects_using_scaled_fitness() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let mut ga = GeneticAlgorithm::new(
+            RouletteWheelSelection::new(),
+            UniformCrossover::new(),
+            GaussianMutation::new(0.0, 0.0),
+        )
+        .with_fitness_scaling(FitnessScaling::Windowing);
+
+        // After windowing, the first individual has no chance at all
+        let population = vec![
+            create_individual(&[-5.0, -5.0]),
+            create_individual(&[1.0, 1.0]),
+            create_individual(&[2.0, 2.0]),
+        ];
+
+        for _ in 0..10 {
+            let (children, stats) = ga.evolve(&population, &mut rng);
+
+            assert_eq!(stats.min_fitness(), -10.0);
+            assert!(children.iter().all(|child| child.fitness() > 0.0));
+        }
+    }
+
+    mod elitism {
+        use super::*;
+
+        fn ga(
+            elitism: Elitism,
+        ) -> GeneticAlgorithm<RouletteWheelSelection, UniformCrossover, GaussianMutation> {
+            GeneticAlgorithm::new(
+                RouletteWheelSelection::new(),
+                UniformCrossover::new(),
+                GaussianMutation::new(1.0, 0.5),
+            )
+            .with_elitism(elitism)
+        }
+
+        fn population() -> Vec<TestIndividual> {
+            vec![
+                create_individual(&[1.0, 1.0, 1.0]), // fitness = 3.0
+                create_individual(&[1.0, 2.0, 4.0]), // fitness = 7.0
+                create_individual(&[0.0, 1.0, 0.0]), // fitness = 1.0
+                create_individual(&[4.0, 1.0, 2.0]), // fitness = 7.0
+                create_individual(&[1.0, 2.0, 1.0]), // fitness = 4.0
+            ]
+        }
+
+        #[test]
+        fn keeps_the_fittest_individuals_unchanged() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let population = population();
+
+            let (new_population, stats) = ga(Elitism::Count(3)).evolve(&population, &mut rng);
+
+            assert_eq!(new_population.len(), population.len());
+
+            // Ties are broken by the position in the population
+            assert_eq!(stats.elites(), [1, 3, 4]);
+
+            assert_eq!(
+                new_population[..3],
+                [
+                    population[1].clone(),
+                    population[3].clone(),
+                    population[4].clone(),
+                ]
+            );
+
+            // ... while everybody else gets mutated
+            for child in &new_population[3..] {
+                assert!(!population.contains(child));
+            }
+        }
+
+        #[test]
+        fn keeps_the_best_individual_across_generations() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut population = population();
+
+            for _ in 0..10 {
+                let best = population
+                    .iter()
+                    .map(|individual| individual.fitness())
+                    .fold(f32::MIN, f32::max);
+
+                let stats;
+                (population, stats) = ga(Elitism::Count(1)).evolve(&population, &mut rng);
+
+                assert_eq!(stats.max_fitness(), best);
+                assert!(population[0].fitness() >= best);
+            }
+        }
+
+        #[test]
+        fn never_picks_nan_fitness() {
+            let population = vec![
+                TestIndividual::new(f32::NAN),
+                TestIndividual::new(1.0),
+                TestIndividual::new(-f32::NAN),
+                TestIndividual::new(f32::NEG_INFINITY),
+                TestIndividual::new(2.0),
+            ];
+
+            assert_eq!(elites(&population, 3), [4, 1, 3]);
+        }
+
+        #[test]
+        fn counts_elites() {
+            assert_eq!(Elitism::None.count(10), 0);
+            assert_eq!(Elitism::Count(3).count(10), 3);
+            assert_eq!(Elitism::Count(30).count(10), 10);
+            assert_eq!(Elitism::Fraction(0.25).count(10), 2);
+            assert_eq!(Elitism::Fraction(1.0).count(10), 10);
+        }
+
+        #[test]
+        fn without_elitism_reports_no_elites() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let (_, stats) = ga(Elitism::None).evolve(&population(), &mut rng);
+
+            assert!(stats.elites().is_empty());
+        }
+    }
 }
@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::{Rng, RngCore};
+
+/// NEAT-style genome: unlike [`Chromosome`](crate::Chromosome), it
+/// describes not only the weights, but also the shape of a network, so
+/// that evolution can grow (or shrink) it.
+///
+/// Connections are kept sorted by their innovation numbers, which is what
+/// allows [`NeatCrossover`](crate::NeatCrossover) to line two genomes up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Genome {
+    nodes: Vec<NodeGene>,
+    connections: Vec<ConnectionGene>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Input,
+    Hidden,
+    Output,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeGene {
+    pub id: usize,
+    pub kind: NodeKind,
+    pub bias: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectionGene {
+    /// Historical marker - the same structural change (e.g. connecting
+    /// node 3 to node 7) gets the same number in every genome.
+    pub innovation: usize,
+    pub from: usize,
+    pub to: usize,
+    pub weight: f32,
+    pub enabled: bool,
+}
+
+/// Hands out innovation numbers and node ids, making sure that genomes
+/// which independently made the same structural change agree on them.
+///
+/// A single history should be shared by the whole population.
+#[derive(Clone, Debug)]
+pub struct InnovationHistory {
+    inputs: usize,
+    outputs: usize,
+    next_innovation: usize,
+    next_node: usize,
+    connections: HashMap<(usize, usize), usize>,
+    splits: HashMap<usize, usize>,
+}
+
+impl Genome {
+    pub fn new(nodes: Vec<NodeGene>, mut connections: Vec<ConnectionGene>) -> Self {
+        connections.sort_by_key(|conn| conn.innovation);
+
+        Self { nodes, connections }
+    }
+
+    /// Creates a genome with every input connected straight to every
+    /// output, and no hidden nodes - the usual NEAT starting point.
+    pub fn minimal(history: &mut InnovationHistory, rng: &mut dyn RngCore) -> Self {
+        let inputs = 0..history.inputs;
+        let outputs = history.inputs..(history.inputs + history.outputs);
+
+        let nodes = inputs
+            .clone()
+            .map(|id| NodeGene {
+                id,
+                kind: NodeKind::Input,
+                bias: 0.0,
+            })
+            .chain(outputs.clone().map(|id| NodeGene {
+                id,
+                kind: NodeKind::Output,
+                bias: rng.gen_range(-1.0..=1.0),
+            }))
+            .collect();
+
+        let mut connections = Vec::new();
+
+        for from in inputs {
+            for to in outputs.clone() {
+                connections.push(ConnectionGene {
+                    innovation: history.connection(from, to),
+                    from,
+                    to,
+                    weight: rng.gen_range(-1.0..=1.0),
+                    enabled: true,
+                });
+            }
+        }
+
+        Self::new(nodes, connections)
+    }
+
+    pub fn nodes(&self) -> &[NodeGene] {
+        &self.nodes
+    }
+
+    pub fn connections(&self) -> &[ConnectionGene] {
+        &self.connections
+    }
+
+    pub(crate) fn node(&self, id: usize) -> Option<&NodeGene> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub(crate) fn nodes_mut(&mut self) -> &mut [NodeGene] {
+        &mut self.nodes
+    }
+
+    pub(crate) fn connections_mut(&mut self) -> &mut [ConnectionGene] {
+        &mut self.connections
+    }
+
+    pub(crate) fn push_node(&mut self, node: NodeGene) {
+        self.nodes.push(node);
+    }
+
+    pub(crate) fn push_connection(&mut self, connection: ConnectionGene) {
+        let idx = self
+            .connections
+            .partition_point(|conn| conn.innovation < connection.innovation);
+
+        self.connections.insert(idx, connection);
+    }
+
+    pub(crate) fn has_connection(&self, from: usize, to: usize) -> bool {
+        self.connections
+            .iter()
+            .any(|conn| conn.from == from && conn.to == to)
+    }
+
+    /// Returns whether `to` can be reached from `from`, following all the
+    /// connections (disabled ones too, since they might get re-enabled).
+    pub(crate) fn is_reachable(&self, from: usize, to: usize) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![from];
+
+        while let Some(node) = pending.pop() {
+            if node == to {
+                return true;
+            }
+
+            if visited.insert(node) {
+                pending.extend(
+                    self.connections
+                        .iter()
+                        .filter(|conn| conn.from == node)
+                        .map(|conn| conn.to),
+                );
+            }
+        }
+
+        false
+    }
+}
+
+impl InnovationHistory {
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        Self {
+            inputs,
+            outputs,
+            next_innovation: 0,
+            next_node: inputs + outputs,
+            connections: HashMap::new(),
+            splits: HashMap::new(),
+        }
+    }
+
+    /// Returns innovation number of a connection going from `from` to
+    /// `to`, allocating a new one if that's the first time we see it.
+    pub fn connection(&mut self, from: usize, to: usize) -> usize {
+        let next_innovation = &mut self.next_innovation;
+
+        *self.connections.entry((from, to)).or_insert_with(|| {
+            *next_innovation += 1;
+            *next_innovation - 1
+        })
+    }
+
+    /// Returns id of the node created by splitting given connection,
+    /// allocating a new one if that's the first time it gets split.
+    pub fn split(&mut self, innovation: usize) -> usize {
+        let next_node = &mut self.next_node;
+
+        *self.splits.entry(innovation).or_insert_with(|| {
+            *next_node += 1;
+            *next_node - 1
+        })
+    }
+
+    /// Allocates a brand new node id.
+    pub fn node(&mut self) -> usize {
+        self.next_node += 1;
+        self.next_node - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn minimal_genome_connects_every_input_to_every_output() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut history = InnovationHistory::new(3, 2);
+        let genome = Genome::minimal(&mut history, &mut rng);
+
+        assert_eq!(genome.nodes().len(), 5);
+        assert_eq!(genome.connections().len(), 6);
+
+        for conn in genome.connections() {
+            assert_eq!(genome.node(conn.from).unwrap().kind, NodeKind::Input);
+            assert_eq!(genome.node(conn.to).unwrap().kind, NodeKind::Output);
+            assert!(conn.enabled);
+        }
+    }
+
+    #[test]
+    fn minimal_genomes_share_innovation_numbers() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut history = InnovationHistory::new(3, 2);
+        let genome_a = Genome::minimal(&mut history, &mut rng);
+        let genome_b = Genome::minimal(&mut history, &mut rng);
+
+        let innovations = |genome: &Genome| -> Vec<_> {
+            genome
+                .connections()
+                .iter()
+                .map(|conn| (conn.innovation, conn.from, conn.to))
+                .collect()
+        };
+
+        assert_eq!(innovations(&genome_a), innovations(&genome_b));
+        assert_ne!(
+            genome_a.connections()[0].weight,
+            genome_b.connections()[0].weight
+        );
+    }
+
+    #[test]
+    fn history_remembers_splits() {
+        let mut history = InnovationHistory::new(2, 1);
+
+        assert_eq!(history.split(0), 3);
+        assert_eq!(history.split(1), 4);
+        assert_eq!(history.split(0), 3);
+        assert_eq!(history.node(), 5);
+    }
+}
@@ -1,12 +1,15 @@
 pub use self::{
-    chromosome::*, crossover::*, genetic_algorithm::*, individual::*, mutation::*, selection::*,
+    chromosome::*, crossover::*, genetic_algorithm::*, genome::*, individual::*, mutation::*,
+    scaling::*, selection::*,
 };
 
 mod chromosome;
 mod crossover;
 mod genetic_algorithm;
+mod genome;
 mod individual;
 mod mutation;
+mod scaling;
 mod selection;
 
 #[derive(Clone, Debug)]
@@ -14,9 +17,10 @@ pub struct Statistics {
     min_fitness: f32,
     max_fitness: f32,
     avg_fitness: f32,
+    elites: Vec<usize>,
 }
 impl Statistics {
-    pub(crate) fn new<I>(population: &[I]) -> Self
+    pub(crate) fn new<I>(population: &[I], elites: Vec<usize>) -> Self
     where
         I: Individual,
     {
@@ -38,6 +42,7 @@ impl Statistics {
             min_fitness,
             max_fitness,
             avg_fitness: sum_fitness / (population.len() as f32),
+            elites,
         }
     }
 
@@ -52,4 +57,12 @@ impl Statistics {
     pub fn avg_fitness(&self) -> f32 {
         self.avg_fitness
     }
+
+    /// Returns indices (into the population passed to
+    /// [`GeneticAlgorithm::evolve()`]) of individuals that got carried
+    /// over unchanged, fittest first - in the new population, they occupy
+    /// positions `0..elites.len()` (see [`Elitism`]).
+    pub fn elites(&self) -> &[usize] {
+        &self.elites
+    }
 }
@@ -1,11 +1,18 @@
 use rand::{Rng, RngCore};
+use rand_distr::{Distribution, StandardNormal};
 
 use crate::*;
 
 pub trait MutationMethod {
     fn mutate(&self, child: &mut Chromosome, rng: &mut dyn RngCore);
+
+    /// Called by [`GeneticAlgorithm::evolve()`] once all children of a
+    /// generation got mutated - e.g. so that [`ScheduledMutation`] knows
+    /// when to decay.
+    fn next_generation(&mut self) {}
 }
 
+/// Adds normally distributed noise to randomly chosen genes.
 #[derive(Clone, Debug)]
 pub struct GaussianMutation {
     /// Probability of changing a gene:
@@ -13,9 +20,10 @@ pub struct GaussianMutation {
     /// - 1.0 = all genes will be touched
     chance: f32,
 
-    /// Magnitude of that change:
+    /// Magnitude (standard deviation) of that change:
     /// - 0.0 = touched genes will not be modified
-    /// - 3.0 = touched genes will be += or -= by at most 3.0
+    /// - 3.0 = touched genes will be += or -= by less than 3.0 about
+    ///   two-thirds of the time
     coeff: f32,
 }
 
@@ -27,16 +35,328 @@ impl GaussianMutation {
     }
 }
 
+impl GaussianMutation {
+    pub(crate) fn mutate_gene(&self, gene: f32, rng: &mut dyn RngCore) -> f32 {
+        if rng.gen_bool(self.chance as _) {
+            gene + self.coeff * normal(rng)
+        } else {
+            gene
+        }
+    }
+}
+
 impl MutationMethod for GaussianMutation {
     fn mutate(&self, child: &mut Chromosome, rng: &mut dyn RngCore) {
         for gene in child.iter_mut() {
-            let sign = if rng.gen_bool(0.5) { -1.0 } else { 1.0 };
+            *gene = self.mutate_gene(*gene, rng);
+        }
+    }
+}
+
+/// Evolution-strategy style mutation, where each gene comes with its own
+/// step size that evolves alongside it - so that the population itself
+/// figures out how much to explore, gene by gene.
+///
+/// Step sizes live in the chromosome: its first half are the genes, and
+/// the second half are their step sizes (see
+/// [`SelfAdaptiveMutation::chromosome()`] and
+/// [`SelfAdaptiveMutation::genes()`]). Each mutation first scales the
+/// step sizes by a log-normally distributed factor, and then adds normal
+/// noise of that size to every gene.
+#[derive(Clone, Debug)]
+pub struct SelfAdaptiveMutation {
+    /// Step sizes never get smaller than this, so that genes can't stop
+    /// evolving altogether
+    min_step: f32,
+}
+
+impl SelfAdaptiveMutation {
+    pub fn new(min_step: f32) -> Self {
+        assert!(min_step > 0.0);
+
+        Self { min_step }
+    }
+
+    /// Creates a chromosome out of given genes, with the same initial
+    /// step size for each of them.
+    pub fn chromosome(genes: impl IntoIterator<Item = f32>, step: f32) -> Chromosome {
+        let genes: Vec<_> = genes.into_iter().collect();
+        let steps = vec![step; genes.len()];
+
+        genes.into_iter().chain(steps).collect()
+    }
+
+    /// Returns the actual genes of a chromosome, without step sizes.
+    pub fn genes(chromosome: &Chromosome) -> impl Iterator<Item = &f32> {
+        chromosome.iter().take(chromosome.len() / 2)
+    }
+}
+
+impl Default for SelfAdaptiveMutation {
+    fn default() -> Self {
+        Self::new(1e-3)
+    }
+}
+
+impl MutationMethod for SelfAdaptiveMutation {
+    fn mutate(&self, child: &mut Chromosome, rng: &mut dyn RngCore) {
+        assert!(
+            child.len().is_multiple_of(2),
+            "got a chromosome without step sizes"
+        );
+
+        let half = child.len() / 2;
+        let n = half as f32;
+
+        // Learning rates recommended by Schwefel
+        let global_rate = 1.0 / (2.0 * n).sqrt();
+        let local_rate = 1.0 / (2.0 * n.sqrt()).sqrt();
+        let global = global_rate * normal(rng);
+
+        let mut values: Vec<_> = child.iter_mut().collect();
+        let (genes, steps) = values.split_at_mut(half);
+
+        for (gene, step) in genes.iter_mut().zip(steps) {
+            **step = (**step * (global + local_rate * normal(rng)).exp()).max(self.min_step);
+            **gene += **step * normal(rng);
+        }
+    }
+}
+
+/// Like [`GaussianMutation`], but with chance and magnitude that change
+/// over generations - e.g. exploring a lot at first, and fine-tuning
+/// later on.
+#[derive(Clone, Debug)]
+pub struct ScheduledMutation {
+    chance: Schedule,
+    coeff: Schedule,
+    generation: usize,
+}
+
+/// How a parameter of [`ScheduledMutation`] changes over generations.
+#[derive(Clone, Copy, Debug)]
+pub enum Schedule {
+    Constant(f32),
+
+    /// Goes linearly from `from` to `to` over given number of
+    /// generations, and then stays at `to`
+    Linear {
+        from: f32,
+        to: f32,
+        generations: usize,
+    },
+
+    /// Starts at `from` and gets multiplied by `factor` each generation,
+    /// but never goes below `min`
+    Exponential {
+        from: f32,
+        factor: f32,
+        min: f32,
+    },
+}
 
-            if rng.gen_bool(self.chance as _) {
-                *gene += sign * self.coeff * rng.gen::<f32>();
+impl Schedule {
+    /// Returns value of the parameter in given generation (counting from
+    /// zero).
+    pub fn value(&self, generation: usize) -> f32 {
+        match *self {
+            Self::Constant(value) => value,
+
+            Self::Linear {
+                from,
+                to,
+                generations,
+            } => {
+                let progress = (generation as f32 / generations.max(1) as f32).min(1.0);
+
+                from + (to - from) * progress
             }
+
+            Self::Exponential { from, factor, min } => {
+                (from * factor.powi(generation as i32)).max(min)
+            }
+        }
+    }
+}
+
+impl ScheduledMutation {
+    pub fn new(chance: Schedule, coeff: Schedule) -> Self {
+        Self {
+            chance,
+            coeff,
+            generation: 0,
         }
     }
+
+    /// Returns the generation this mutation is currently at, i.e. how
+    /// many times [`MutationMethod::next_generation()`] has been called.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Returns the [`GaussianMutation`] used in current generation.
+    pub fn current(&self) -> GaussianMutation {
+        GaussianMutation::new(
+            self.chance.value(self.generation).clamp(0.0, 1.0),
+            self.coeff.value(self.generation),
+        )
+    }
+}
+
+impl MutationMethod for ScheduledMutation {
+    fn mutate(&self, child: &mut Chromosome, rng: &mut dyn RngCore) {
+        self.current().mutate(child, rng);
+    }
+
+    fn next_generation(&mut self) {
+        self.generation += 1;
+    }
+}
+
+fn normal(rng: &mut dyn RngCore) -> f32 {
+    StandardNormal.sample(rng)
+}
+
+/// Mutation of [`Genome`]s: perturbs weights & biases (just like
+/// [`GaussianMutation`] does for chromosomes), and occasionally changes
+/// the genome's structure by adding a connection or a node.
+///
+/// Structural mutations keep the genome acyclic, so that it can be
+/// evaluated as a feed-forward DAG.
+#[derive(Clone, Debug)]
+pub struct NeatMutation {
+    weights: GaussianMutation,
+
+    /// Probability of connecting two so-far-unconnected nodes
+    add_connection_chance: f32,
+
+    /// Probability of splitting a connection in two, with a new node in
+    /// between
+    add_node_chance: f32,
+}
+
+impl NeatMutation {
+    /// How many random node pairs to try before giving up on adding a
+    /// connection (e.g. because the genome is already fully connected)
+    const ADD_CONNECTION_ATTEMPTS: usize = 20;
+
+    pub fn new(chance: f32, coeff: f32, add_connection_chance: f32, add_node_chance: f32) -> Self {
+        assert!((0.0..=1.0).contains(&add_connection_chance));
+        assert!((0.0..=1.0).contains(&add_node_chance));
+
+        Self {
+            weights: GaussianMutation::new(chance, coeff),
+            add_connection_chance,
+            add_node_chance,
+        }
+    }
+
+    pub fn mutate(
+        &self,
+        genome: &mut Genome,
+        history: &mut InnovationHistory,
+        rng: &mut dyn RngCore,
+    ) {
+        for conn in genome.connections_mut() {
+            conn.weight = self.weights.mutate_gene(conn.weight, rng);
+        }
+
+        for node in genome.nodes_mut() {
+            if node.kind != NodeKind::Input {
+                node.bias = self.weights.mutate_gene(node.bias, rng);
+            }
+        }
+
+        if rng.gen_bool(self.add_connection_chance as _) {
+            Self::add_connection(genome, history, rng);
+        }
+
+        if rng.gen_bool(self.add_node_chance as _) {
+            Self::add_node(genome, history, rng);
+        }
+    }
+
+    fn add_connection(genome: &mut Genome, history: &mut InnovationHistory, rng: &mut dyn RngCore) {
+        let nodes = genome.nodes();
+
+        for _ in 0..Self::ADD_CONNECTION_ATTEMPTS {
+            let from = &nodes[rng.gen_range(0..nodes.len())];
+            let to = &nodes[rng.gen_range(0..nodes.len())];
+
+            if from.kind == NodeKind::Output || to.kind == NodeKind::Input || from.id == to.id {
+                continue;
+            }
+
+            // If `from` is reachable from `to`, connecting them would
+            // create a cycle
+            if genome.has_connection(from.id, to.id) || genome.is_reachable(to.id, from.id) {
+                continue;
+            }
+
+            let (from, to) = (from.id, to.id);
+
+            genome.push_connection(ConnectionGene {
+                innovation: history.connection(from, to),
+                from,
+                to,
+                weight: rng.gen_range(-1.0..=1.0),
+                enabled: true,
+            });
+
+            return;
+        }
+    }
+
+    fn add_node(genome: &mut Genome, history: &mut InnovationHistory, rng: &mut dyn RngCore) {
+        let enabled: Vec<_> = genome
+            .connections()
+            .iter()
+            .enumerate()
+            .filter(|(_, conn)| conn.enabled)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let Some(&idx) = enabled.get(rng.gen_range(0..enabled.len().max(1))) else {
+            return;
+        };
+
+        let conn = &mut genome.connections_mut()[idx];
+        conn.enabled = false;
+
+        let (innovation, from, to, weight) = (conn.innovation, conn.from, conn.to, conn.weight);
+
+        // Same split might've already happened in this genome (if the
+        // connection got re-enabled later); in that case we need a fresh
+        // node, otherwise we'd end up with duplicated genes
+        let node = match history.split(innovation) {
+            node if genome.node(node).is_some() => history.node(),
+            node => node,
+        };
+
+        genome.push_node(NodeGene {
+            id: node,
+            kind: NodeKind::Hidden,
+            bias: 0.0,
+        });
+
+        // Weights chosen so that the new node initially (almost, given
+        // the activation function) doesn't change what the genome does
+        genome.push_connection(ConnectionGene {
+            innovation: history.connection(from, node),
+            from,
+            to: node,
+            weight: 1.0,
+            enabled: true,
+        });
+
+        genome.push_connection(ConnectionGene {
+            innovation: history.connection(node, to),
+            from: node,
+            to,
+            weight,
+            enabled: true,
+        });
+    }
 }
 
 #[cfg(test)]
@@ -44,7 +364,7 @@ mod tests {
     use rand::SeedableRng;
     use rand_chacha::ChaCha8Rng;
 
-    use super::{GaussianMutation, MutationMethod};
+    use crate::*;
 
     fn get_actual_child(chance: f32, coeff: f32) -> Vec<f32> {
         let mut child = vec![1.0, 2.0, 3.0, 4.0, 5.0].into_iter().collect();
@@ -106,7 +426,7 @@ mod tests {
             #[test]
             fn slightly_changes_the_original_chromosome() {
                 let actual = get_actual_child(0.5);
-                let expected = vec![1.0, 1.7756249, 3.0, 4.1596804, 5.0];
+                let expected = vec![1.0, 2.0, 2.0324764, 3.467692, 4.4987187];
 
                 approx::assert_relative_eq!(actual.as_slice(), expected.as_slice(),);
             }
@@ -136,10 +456,253 @@ mod tests {
             fn entirely_changes_the_original_chromosome() {
                 let actual = get_actual_child(0.5);
 
-                let expected = vec![1.4545316, 2.1162078, 2.7756248, 3.9505124, 4.638691];
+                let expected = vec![1.6888486, 2.2026734, 2.4018655, 3.0324764, 4.664113];
 
                 approx::assert_relative_eq!(actual.as_slice(), expected.as_slice(),);
             }
         }
     }
+
+    #[test]
+    fn gaussian_mutation_is_normally_distributed() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut child: Chromosome = vec![0.0; 10_000].into_iter().collect();
+
+        GaussianMutation::new(1.0, 0.5).mutate(&mut child, &mut rng);
+
+        let genes: Vec<_> = child.into_iter().collect();
+        let mean = genes.iter().sum::<f32>() / genes.len() as f32;
+        let variance =
+            genes.iter().map(|gene| (gene - mean).powi(2)).sum::<f32>() / genes.len() as f32;
+
+        approx::assert_abs_diff_eq!(mean, 0.0, epsilon = 0.02);
+        approx::assert_abs_diff_eq!(variance.sqrt(), 0.5, epsilon = 0.02);
+
+        // Roughly 5% of the changes should exceed two standard deviations,
+        // which a uniform perturbation of the same magnitude would never do
+        let outliers = genes.iter().filter(|gene| gene.abs() > 1.0).count();
+
+        assert_eq!(outliers, 463);
+    }
+
+    mod self_adaptive_mutation {
+        use super::*;
+
+        #[test]
+        fn mutates_both_genes_and_step_sizes() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut child = SelfAdaptiveMutation::chromosome(vec![1.0, 2.0, 3.0], 0.5);
+
+            SelfAdaptiveMutation::default().mutate(&mut child, &mut rng);
+
+            let actual: Vec<_> = child.into_iter().collect();
+            let expected = vec![
+                -0.30511296,
+                1.7915821,
+                1.9357346, // genes
+                1.0909863,
+                0.31025016,
+                0.49524295, // step sizes
+            ];
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_slice());
+        }
+
+        #[test]
+        fn step_sizes_control_how_much_genes_change() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mutation = SelfAdaptiveMutation::new(1e-6);
+
+            let mut small = SelfAdaptiveMutation::chromosome(vec![0.0; 100], 1e-3);
+            let mut big = SelfAdaptiveMutation::chromosome(vec![0.0; 100], 1.0);
+
+            mutation.mutate(&mut small, &mut rng);
+            mutation.mutate(&mut big, &mut rng);
+
+            let spread = |chromosome: &Chromosome| -> f32 {
+                SelfAdaptiveMutation::genes(chromosome)
+                    .map(|gene| gene.abs())
+                    .sum()
+            };
+
+            assert!(spread(&small) * 100.0 < spread(&big));
+        }
+
+        #[test]
+        fn keeps_step_sizes_above_minimum() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mutation = SelfAdaptiveMutation::new(0.1);
+            let mut child = SelfAdaptiveMutation::chromosome(vec![0.0; 10], 0.1);
+
+            for _ in 0..100 {
+                mutation.mutate(&mut child, &mut rng);
+            }
+
+            assert!(child.iter().skip(10).all(|&step| step >= 0.1));
+        }
+    }
+
+    mod scheduled_mutation {
+        use super::*;
+
+        #[test]
+        fn schedules() {
+            let linear = Schedule::Linear {
+                from: 1.0,
+                to: 0.5,
+                generations: 10,
+            };
+
+            let exponential = Schedule::Exponential {
+                from: 1.0,
+                factor: 0.5,
+                min: 0.1,
+            };
+
+            approx::assert_relative_eq!(Schedule::Constant(0.3).value(100), 0.3);
+            approx::assert_relative_eq!(linear.value(0), 1.0);
+            approx::assert_relative_eq!(linear.value(4), 0.8);
+            approx::assert_relative_eq!(linear.value(20), 0.5);
+            approx::assert_relative_eq!(exponential.value(2), 0.25);
+            approx::assert_relative_eq!(exponential.value(20), 0.1);
+        }
+
+        #[test]
+        fn decays_over_generations() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+            let mut mutation = ScheduledMutation::new(
+                Schedule::Linear {
+                    from: 1.0,
+                    to: 0.0,
+                    generations: 5,
+                },
+                Schedule::Constant(0.5),
+            );
+
+            let original: Chromosome = vec![1.0, 2.0, 3.0, 4.0, 5.0].into_iter().collect();
+            let mut changed = Vec::new();
+
+            for _ in 0..6 {
+                let mut child = original.clone();
+                mutation.mutate(&mut child, &mut rng);
+                mutation.next_generation();
+
+                changed.push(
+                    child
+                        .iter()
+                        .zip(original.iter())
+                        .filter(|(c, o)| c != o)
+                        .count(),
+                );
+            }
+
+            assert_eq!(mutation.generation(), 6);
+            assert_eq!(changed, [5, 5, 1, 3, 2, 0]);
+        }
+    }
+
+    mod neat_mutation {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::*;
+
+        fn genome(history: &mut InnovationHistory, rng: &mut ChaCha8Rng) -> Genome {
+            Genome::minimal(history, rng)
+        }
+
+        #[test]
+        fn without_structural_chances_keeps_the_structure() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(3, 2);
+            let mut genome = genome(&mut history, &mut rng);
+            let original = genome.clone();
+
+            NeatMutation::new(1.0, 0.5, 0.0, 0.0).mutate(&mut genome, &mut history, &mut rng);
+
+            assert_eq!(genome.nodes().len(), original.nodes().len());
+            assert_eq!(genome.connections().len(), original.connections().len());
+            assert_ne!(genome, original);
+        }
+
+        #[test]
+        fn adding_node_splits_a_connection() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(2, 1);
+            let mut genome = genome(&mut history, &mut rng);
+
+            NeatMutation::new(0.0, 0.0, 0.0, 1.0).mutate(&mut genome, &mut history, &mut rng);
+
+            assert_eq!(genome.nodes().len(), 4);
+            assert_eq!(genome.nodes()[3].kind, NodeKind::Hidden);
+            assert_eq!(genome.connections().len(), 4);
+
+            let disabled: Vec<_> = genome
+                .connections()
+                .iter()
+                .filter(|conn| !conn.enabled)
+                .collect();
+
+            assert_eq!(disabled.len(), 1);
+
+            let split = disabled[0];
+            let node = genome.nodes()[3].id;
+
+            let into_node = genome
+                .connections()
+                .iter()
+                .find(|conn| conn.from == split.from && conn.to == node)
+                .unwrap();
+
+            let out_of_node = genome
+                .connections()
+                .iter()
+                .find(|conn| conn.from == node && conn.to == split.to)
+                .unwrap();
+
+            assert_eq!(into_node.weight, 1.0);
+            assert_eq!(out_of_node.weight, split.weight);
+        }
+
+        #[test]
+        fn same_split_gets_same_innovations_across_genomes() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(1, 1);
+            let mut genome_a = genome(&mut history, &mut rng);
+            let mut genome_b = genome(&mut history, &mut rng);
+
+            let mutation = NeatMutation::new(0.0, 0.0, 0.0, 1.0);
+            mutation.mutate(&mut genome_a, &mut history, &mut rng);
+            mutation.mutate(&mut genome_b, &mut history, &mut rng);
+
+            let structure = |genome: &Genome| -> Vec<_> {
+                genome
+                    .connections()
+                    .iter()
+                    .map(|conn| (conn.innovation, conn.from, conn.to))
+                    .collect()
+            };
+
+            assert_eq!(structure(&genome_a), structure(&genome_b));
+        }
+
+        #[test]
+        fn never_creates_cycles() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut history = InnovationHistory::new(3, 2);
+            let mut genome = genome(&mut history, &mut rng);
+            let mutation = NeatMutation::new(0.1, 0.5, 0.8, 0.3);
+
+            for _ in 0..200 {
+                mutation.mutate(&mut genome, &mut history, &mut rng);
+            }
+
+            assert!(genome.nodes().len() > 5);
+
+            for conn in genome.connections() {
+                assert!(!genome.is_reachable(conn.to, conn.from));
+            }
+        }
+    }
 }
@@ -0,0 +1,171 @@
+/// Transforms raw fitness before [`GeneticAlgorithm::evolve()`] hands
+/// the population over to its [`SelectionMethod`] - e.g. so that a single
+/// lucky bird doesn't dominate the roulette, or so that the differences
+/// don't fade away once everybody gets reasonably good.
+///
+/// Apart from [`FitnessScaling::None`], scaled fitness is never negative;
+/// raw fitness (the one reported in [`Statistics`] and used for
+/// [`Elitism`]) stays as it was.
+///
+/// [`GeneticAlgorithm::evolve()`]: crate::GeneticAlgorithm::evolve
+/// [`SelectionMethod`]: crate::SelectionMethod
+/// [`Statistics`]: crate::Statistics
+/// [`Elitism`]: crate::Elitism
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FitnessScaling {
+    /// Leaves fitness as it is.
+    #[default]
+    None,
+
+    /// Maps fitness linearly, so that the average individual ends up
+    /// with 1.0 and the best one with `multiplier` (e.g. 2.0); whatever
+    /// would end up below zero becomes zero.
+    Linear { multiplier: f32 },
+
+    /// Subtracts `average - c * standard deviation`, so that only the
+    /// individuals within `c` deviations below the average (or better)
+    /// keep any fitness.
+    SigmaTruncation { c: f32 },
+
+    /// Subtracts the worst fitness of the population, so that only the
+    /// differences between individuals matter.
+    Windowing,
+
+    /// Raises fitness to given power (negative fitness counting as
+    /// zero) - above 1.0 increases selection pressure, below 1.0
+    /// decreases it.
+    PowerLaw { exponent: f32 },
+}
+
+impl FitnessScaling {
+    pub fn scale(&self, fitness: &[f32]) -> Vec<f32> {
+        if fitness.is_empty() || *self == Self::None {
+            return fitness.to_vec();
+        }
+
+        let count = fitness.len() as f32;
+        let avg = fitness.iter().sum::<f32>() / count;
+        let max = fitness.iter().copied().fold(f32::MIN, f32::max);
+        let min = fitness.iter().copied().fold(f32::MAX, f32::min);
+
+        let std_dev = (fitness
+            .iter()
+            .map(|&fitness| (fitness - avg).powi(2))
+            .sum::<f32>()
+            / count)
+            .sqrt();
+
+        let scaled = fitness.iter().map(|&fitness| match *self {
+            Self::None => fitness,
+
+            Self::Linear { multiplier } => {
+                if max > avg {
+                    1.0 + (multiplier - 1.0) * (fitness - avg) / (max - avg)
+                } else {
+                    1.0
+                }
+            }
+
+            Self::SigmaTruncation { c } => fitness - (avg - c * std_dev),
+
+            Self::Windowing => fitness - min,
+            Self::PowerLaw { exponent } => fitness.max(0.0).powf(exponent),
+        });
+
+        scaled.map(|fitness| fitness.max(0.0)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FITNESS: [f32; 5] = [1.0, 2.0, 3.0, 4.0, 10.0];
+
+    fn assert_scales(scaling: FitnessScaling, fitness: &[f32], expected: &[f32]) {
+        approx::assert_relative_eq!(scaling.scale(fitness).as_slice(), expected, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn none() {
+        assert_scales(FitnessScaling::None, &FITNESS, &FITNESS);
+        assert_scales(FitnessScaling::None, &[-1.0, -2.0], &[-1.0, -2.0]);
+    }
+
+    #[test]
+    fn linear() {
+        // avg = 4.0, max = 10.0
+        assert_scales(
+            FitnessScaling::Linear { multiplier: 2.0 },
+            &FITNESS,
+            &[0.5, 2.0 / 3.0, 5.0 / 6.0, 1.0, 2.0],
+        );
+
+        // ... which, for a big multiplier, pushes the worst ones to zero
+        assert_scales(
+            FitnessScaling::Linear { multiplier: 5.0 },
+            &FITNESS,
+            &[0.0, 0.0, 1.0 / 3.0, 1.0, 5.0],
+        );
+    }
+
+    #[test]
+    fn sigma_truncation() {
+        // avg = 4.0, standard deviation = sqrt(10.0)
+        let std_dev = 10.0f32.sqrt();
+
+        assert_scales(
+            FitnessScaling::SigmaTruncation { c: 1.0 },
+            &FITNESS,
+            &[
+                std_dev - 3.0,
+                std_dev - 2.0,
+                std_dev - 1.0,
+                std_dev,
+                std_dev + 6.0,
+            ],
+        );
+    }
+
+    #[test]
+    fn windowing() {
+        assert_scales(
+            FitnessScaling::Windowing,
+            &[-3.0, -1.0, 2.0],
+            &[0.0, 2.0, 5.0],
+        );
+    }
+
+    #[test]
+    fn power_law() {
+        assert_scales(
+            FitnessScaling::PowerLaw { exponent: 2.0 },
+            &[-1.0, 0.5, 3.0],
+            &[0.0, 0.25, 9.0],
+        );
+    }
+
+    #[test]
+    fn handles_degenerate_populations() {
+        let scalings = [
+            FitnessScaling::Linear { multiplier: 2.0 },
+            FitnessScaling::SigmaTruncation { c: 2.0 },
+            FitnessScaling::Windowing,
+            FitnessScaling::PowerLaw { exponent: 0.5 },
+        ];
+
+        for scaling in scalings {
+            for fitness in [[0.0; 3], [-2.0; 3], [5.0; 3]] {
+                let scaled = scaling.scale(&fitness);
+
+                assert_eq!(scaled.len(), 3);
+                assert!(scaled.iter().all(|&fitness| fitness >= 0.0));
+
+                // Nobody's better than anybody else
+                assert!(scaled.iter().all(|&fitness| fitness == scaled[0]));
+            }
+        }
+
+        assert!(FitnessScaling::Windowing.scale(&[]).is_empty());
+    }
+}
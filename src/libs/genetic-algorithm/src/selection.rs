@@ -1,13 +1,66 @@
-use rand::prelude::SliceRandom;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    prelude::SliceRandom,
+    Rng,
+};
 
 use crate::*;
 
+/// Selection works on fitness alone - so that e.g. [`GeneticAlgorithm`]
+/// can select using fitness transformed by [`FitnessScaling`] - and
+/// returns indices into it; [`SelectionMethod::select()`] and
+/// [`SelectionMethod::select_many()`] map them back onto individuals.
 pub trait SelectionMethod {
+    /// Selects a single individual, given fitness of the entire
+    /// population, returning its index.
+    fn select_index(&mut self, fitness: &[f32], rng: &mut dyn rand::RngCore) -> usize;
+
+    /// Selects the whole mating pool at once, e.g. both parents of each
+    /// child of the next generation, returning their indices.
+    ///
+    /// By default that's just calling [`SelectionMethod::select_index()`]
+    /// over and over, but some methods (like
+    /// [`StochasticUniversalSampling`]) only work as intended when they
+    /// see the entire pool.
+    fn select_indices(
+        &mut self,
+        fitness: &[f32],
+        count: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Vec<usize> {
+        (0..count)
+            .map(|_| self.select_index(fitness, rng))
+            .collect()
+    }
+
     fn select<'a, I>(&mut self, population: &'a [I], rng: &mut dyn rand::RngCore) -> &'a I
     where
-        I: Individual;
+        I: Individual,
+    {
+        &population[self.select_index(&fitness(population), rng)]
+    }
+
+    fn select_many<'a, I>(
+        &mut self,
+        population: &'a [I],
+        count: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Vec<&'a I>
+    where
+        I: Individual,
+    {
+        self.select_indices(&fitness(population), count, rng)
+            .into_iter()
+            .map(|idx| &population[idx])
+            .collect()
+    }
 }
 
+/// Selects individuals with probability proportional to their fitness.
+///
+/// Negative (and NaN) fitness counts as zero, while infinite fitness
+/// beats any finite one; if that leaves everybody at zero, everybody has
+/// the same chance of being selected.
 pub struct RouletteWheelSelection;
 
 impl RouletteWheelSelection {
@@ -23,16 +76,295 @@ impl Default for RouletteWheelSelection {
 }
 
 impl SelectionMethod for RouletteWheelSelection {
-    fn select<'a, I>(&mut self, population: &'a [I], rng: &mut dyn rand::RngCore) -> &'a I
-    where
-        I: Individual,
-    {
-        population
-            .choose_weighted(rng, |individual| individual.fitness())
-            .expect("Got an empty population")
+    fn select_index(&mut self, fitness: &[f32], rng: &mut dyn rand::RngCore) -> usize {
+        assert!(!fitness.is_empty(), "Got an empty population");
+
+        WeightedIndex::new(roulette_weights(fitness))
+            .unwrap()
+            .sample(rng)
+    }
+}
+
+/// Picks a few random individuals and lets the fittest of them win.
+///
+/// Only the order of fitnesses matters, so - unlike
+/// [`RouletteWheelSelection`] - it doesn't care about their scale, and
+/// works just fine when everybody's fitness is zero.
+#[derive(Clone, Debug)]
+pub struct TournamentSelection {
+    /// How many individuals (drawn with replacement) take part in each
+    /// tournament:
+    /// - 1 = everybody has the same chance of being selected
+    /// - the bigger, the smaller chances of the less fit ones
+    size: usize,
+
+    /// Probability of the fittest contestant winning; if it doesn't, the
+    /// second fittest wins with the same probability, and so on:
+    /// - 1.0 = the fittest contestant always wins
+    /// - 0.0 = the least fit contestant always wins
+    winner_chance: f32,
+}
+
+impl TournamentSelection {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0);
+
+        Self {
+            size,
+            winner_chance: 1.0,
+        }
+    }
+
+    /// Makes tournaments probabilistic (see [`TournamentSelection`]).
+    pub fn with_winner_chance(mut self, winner_chance: f32) -> Self {
+        assert!((0.0..=1.0).contains(&winner_chance));
+
+        self.winner_chance = winner_chance;
+        self
+    }
+}
+
+impl Default for TournamentSelection {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl SelectionMethod for TournamentSelection {
+    fn select_index(&mut self, fitness: &[f32], rng: &mut dyn rand::RngCore) -> usize {
+        assert!(!fitness.is_empty(), "Got an empty population");
+
+        let mut contestants: Vec<_> = (0..self.size)
+            .map(|_| rng.gen_range(0..fitness.len()))
+            .collect();
+
+        // Fittest first; stable, so that ties go to whoever got drawn
+        // first
+        contestants.sort_by(|&a, &b| fitness[b].total_cmp(&fitness[a]));
+
+        let last = contestants.len() - 1;
+
+        for &contestant in &contestants[..last] {
+            if rng.gen_bool(self.winner_chance as _) {
+                return contestant;
+            }
+        }
+
+        contestants[last]
+    }
+}
+
+/// Selects individuals with probability depending on their rank (i.e.
+/// position when sorted by fitness) instead of the fitness itself, so
+/// that a single lucky outlier can't dominate the mating pool.
+///
+/// Individuals of equal fitness share the average of their ranks, so
+/// e.g. a population where everybody's got the same fitness gets
+/// selected uniformly.
+#[derive(Clone, Debug)]
+pub struct RankSelection {
+    ranking: Ranking,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Ranking {
+    /// Probability grows linearly with rank; `pressure` says how many
+    /// times more likely the fittest individual is to get selected than
+    /// an average one:
+    /// - 1.0 = everybody has the same chance of being selected
+    /// - 2.0 = the least fit individual is never selected
+    Linear { pressure: f32 },
+
+    /// Probability grows exponentially with rank - each individual is
+    /// `base` times as likely to get selected as the next fitter one:
+    /// - 1.0 = everybody has the same chance of being selected
+    /// - the smaller, the more likely the fittest ones are selected
+    Exponential { base: f32 },
+}
+
+impl RankSelection {
+    pub fn new(ranking: Ranking) -> Self {
+        match ranking {
+            Ranking::Linear { pressure } => assert!((1.0..=2.0).contains(&pressure)),
+            Ranking::Exponential { base } => assert!(base > 0.0 && base <= 1.0),
+        }
+
+        Self { ranking }
+    }
+
+    pub fn linear(pressure: f32) -> Self {
+        Self::new(Ranking::Linear { pressure })
+    }
+
+    pub fn exponential(base: f32) -> Self {
+        Self::new(Ranking::Exponential { base })
+    }
+
+    /// Returns (not normalized) probability of selecting each individual.
+    fn weights(&self, fitness: &[f32]) -> Vec<f32> {
+        let mut ranked: Vec<_> = (0..fitness.len()).collect();
+
+        // Least fit first, so that rank 0 is the worst one
+        ranked.sort_by(|&a, &b| fitness[a].total_cmp(&fitness[b]));
+
+        let worst_rank = (fitness.len() - 1) as f32;
+        let mut weights = vec![0.0; fitness.len()];
+        let mut start = 0;
+
+        for ties in ranked.chunk_by(|&a, &b| fitness[a] == fitness[b]) {
+            let rank = start as f32 + (ties.len() - 1) as f32 / 2.0;
+
+            let weight = match self.ranking {
+                Ranking::Linear { pressure } => {
+                    (2.0 - pressure) + 2.0 * (pressure - 1.0) * rank / worst_rank
+                }
+                Ranking::Exponential { base } => base.powf(worst_rank - rank),
+            };
+
+            for &idx in ties {
+                weights[idx] = weight;
+            }
+
+            start += ties.len();
+        }
+
+        weights
+    }
+}
+
+impl Default for RankSelection {
+    fn default() -> Self {
+        Self::linear(1.5)
+    }
+}
+
+impl SelectionMethod for RankSelection {
+    fn select_index(&mut self, fitness: &[f32], rng: &mut dyn rand::RngCore) -> usize {
+        self.select_indices(fitness, 1, rng)[0]
+    }
+
+    fn select_indices(
+        &mut self,
+        fitness: &[f32],
+        count: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Vec<usize> {
+        assert!(!fitness.is_empty(), "Got an empty population");
+
+        // With a single individual, linear ranking of pressure 2.0 would
+        // give it no chance at all
+        if fitness.len() == 1 {
+            return vec![0; count];
+        }
+
+        let ranks = WeightedIndex::new(self.weights(fitness)).unwrap();
+
+        (0..count).map(|_| ranks.sample(rng)).collect()
+    }
+}
+
+/// Like [`RouletteWheelSelection`], but spins the wheel only once, with
+/// as many evenly spaced pointers as there are individuals to select -
+/// so the mating pool always gets (almost) exactly the expected number
+/// of copies of each individual, instead of whatever luck provides.
+///
+/// Meant for [`SelectionMethod::select_many()`], which returns the pool
+/// shuffled; for a single individual, it's the same as the roulette.
+///
+/// Negative (and NaN) fitness counts as zero, while infinite fitness
+/// beats any finite one; if that leaves everybody at zero, everybody has
+/// the same chance of being selected.
+#[derive(Clone, Debug, Default)]
+pub struct StochasticUniversalSampling;
+
+impl StochasticUniversalSampling {
+    pub fn new() -> Self {
+        Self
     }
 }
 
+impl SelectionMethod for StochasticUniversalSampling {
+    fn select_index(&mut self, fitness: &[f32], rng: &mut dyn rand::RngCore) -> usize {
+        self.select_indices(fitness, 1, rng)[0]
+    }
+
+    fn select_indices(
+        &mut self,
+        fitness: &[f32],
+        count: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Vec<usize> {
+        assert!(!fitness.is_empty(), "Got an empty population");
+
+        if count == 0 {
+            return Vec::new();
+        }
+
+        let weights = roulette_weights(fitness);
+        let total: f32 = weights.iter().sum();
+        let spacing = total / count as f32;
+        let mut pointer = rng.gen_range(0.0..spacing);
+
+        let mut selected = Vec::with_capacity(count);
+        let mut idx = 0;
+        let mut reached = weights[0];
+
+        while selected.len() < count {
+            // Guards against rounding errors pushing the last pointer
+            // past the end of the wheel
+            while pointer >= reached && idx + 1 < fitness.len() {
+                idx += 1;
+                reached += weights[idx];
+            }
+
+            selected.push(idx);
+            pointer += spacing;
+        }
+
+        selected.shuffle(rng);
+        selected
+    }
+}
+
+fn fitness<I>(population: &[I]) -> Vec<f32>
+where
+    I: Individual,
+{
+    population.iter().map(Individual::fitness).collect()
+}
+
+/// Turns fitness into weights for [`RouletteWheelSelection`] and
+/// [`StochasticUniversalSampling`] (see their docs for the rules).
+///
+/// Weights are divided by the largest one, so that they always add up to
+/// something finite and positive.
+fn roulette_weights(fitness: &[f32]) -> Vec<f32> {
+    // `f32::max()` ignores NaN, so it ends up as zero as well
+    let weight = |fitness: f32| fitness.max(0.0);
+    let max = fitness
+        .iter()
+        .fold(0.0, |max, &fitness| weight(fitness).max(max));
+
+    if max == 0.0 {
+        return vec![1.0; fitness.len()];
+    }
+
+    fitness
+        .iter()
+        .map(|&fitness| {
+            if max.is_infinite() {
+                if weight(fitness) == max {
+                    1.0
+                } else {
+                    0.0
+                }
+            } else {
+                weight(fitness) / max
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
@@ -42,9 +374,48 @@ mod tests {
 
     use crate::{
         individual::{Individual, TestIndividual},
-        selection::{RouletteWheelSelection, SelectionMethod},
+        selection::*,
     };
 
+    fn population() -> Vec<TestIndividual> {
+        vec![
+            TestIndividual::new(2.0),
+            TestIndividual::new(1.0),
+            TestIndividual::new(4.0),
+            TestIndividual::new(3.0),
+        ]
+    }
+
+    /// Selects 1000 times, counting how many times each individual (by
+    /// its index) got chosen.
+    fn indices(method: &mut impl SelectionMethod, fitness: &[f32]) -> BTreeMap<usize, usize> {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        (0..1_000)
+            .map(|_| method.select_index(fitness, &mut rng))
+            .fold(Default::default(), |mut histogram, idx| {
+                *histogram.entry(idx).or_default() += 1;
+                histogram
+            })
+    }
+
+    /// Selects 1000 times, counting how many times each fitness got
+    /// chosen.
+    fn histogram(
+        method: &mut impl SelectionMethod,
+        population: &[TestIndividual],
+    ) -> BTreeMap<i32, usize> {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        (0..1_000)
+            .map(|_| method.select(population, &mut rng))
+            .fold(Default::default(), |mut histogram, individual| {
+                *histogram.entry(individual.fitness() as _).or_default() += 1;
+
+                histogram
+            })
+    }
+
     #[test]
     fn test_selection_method() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
@@ -75,4 +446,318 @@ mod tests {
 
         assert_eq!(actual_histogram, expected_histogram);
     }
+
+    #[test]
+    fn roulette_wheel_treats_negative_fitness_as_zero() {
+        let population = vec![
+            TestIndividual::new(-5.0),
+            TestIndividual::new(1.0),
+            TestIndividual::new(3.0),
+        ];
+
+        let actual_histogram = histogram(&mut RouletteWheelSelection::new(), &population);
+
+        let expected_histogram = maplit::btreemap! {
+            1 => 248,
+            3 => 752,
+        };
+
+        assert_eq!(actual_histogram, expected_histogram);
+    }
+
+    #[test]
+    fn roulette_wheel_works_for_all_zero_fitness() {
+        let population = vec![
+            TestIndividual::new(0.0),
+            TestIndividual::new(-1.0),
+            TestIndividual::new(0.0),
+        ];
+
+        let actual_histogram = histogram(&mut RouletteWheelSelection::new(), &population);
+
+        let expected_histogram = maplit::btreemap! {
+            -1 => 355,
+            0 => 645,
+        };
+
+        assert_eq!(actual_histogram, expected_histogram);
+    }
+
+    #[test]
+    fn roulette_wheel_prefers_infinite_fitness() {
+        let fitness = [1.0, f32::INFINITY, 2.0, f32::NAN];
+
+        let actual = indices(&mut RouletteWheelSelection::new(), &fitness);
+
+        assert_eq!(actual, maplit::btreemap! { 1 => 1_000 });
+    }
+
+    #[test]
+    fn roulette_wheel_works_for_fitness_overflowing_the_total() {
+        let fitness = [3e38, 3e38, 3e38];
+
+        let actual = indices(&mut RouletteWheelSelection::new(), &fitness);
+
+        let expected = maplit::btreemap! {
+            0 => 327,
+            1 => 355,
+            2 => 318,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    mod tournament {
+        use super::*;
+
+        #[test]
+        fn prefers_fitter_individuals() {
+            let actual_histogram = histogram(&mut TournamentSelection::new(2), &population());
+
+            let expected_histogram = maplit::btreemap! {
+                1 => 75,
+                2 => 177,
+                3 => 300,
+                4 => 448,
+            };
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn bigger_tournaments_increase_pressure() {
+            let actual_histogram = histogram(&mut TournamentSelection::new(4), &population());
+
+            let expected_histogram = maplit::btreemap! {
+                1 => 6,
+                2 => 61,
+                3 => 251,
+                4 => 682,
+            };
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn lets_less_fit_individuals_win_sometimes() {
+            let mut method = TournamentSelection::new(4).with_winner_chance(0.5);
+            let actual_histogram = histogram(&mut method, &population());
+
+            let expected_histogram = maplit::btreemap! {
+                1 => 146,
+                2 => 179,
+                3 => 270,
+                4 => 405,
+            };
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn does_not_care_about_fitness_scale() {
+            let scaled: Vec<_> = population()
+                .iter()
+                .map(|individual| TestIndividual::new(individual.fitness() * 1000.0 - 2500.0))
+                .collect();
+
+            let unscaled: Vec<_> = histogram(&mut TournamentSelection::new(2), &population())
+                .into_values()
+                .collect();
+
+            let scaled: Vec<_> = histogram(&mut TournamentSelection::new(2), &scaled)
+                .into_values()
+                .collect();
+
+            assert_eq!(scaled, unscaled);
+        }
+
+        #[test]
+        fn works_for_all_zero_fitness() {
+            let population = vec![TestIndividual::new(0.0); 4];
+            let actual_histogram = histogram(&mut TournamentSelection::new(3), &population);
+
+            assert_eq!(actual_histogram, maplit::btreemap! { 0 => 1_000 });
+        }
+    }
+    mod rank {
+        use super::*;
+
+        #[test]
+        fn linear() {
+            let actual_histogram = histogram(&mut RankSelection::linear(2.0), &population());
+
+            let expected_histogram = maplit::btreemap! {
+                // least fit individual (#1) never gets selected
+                2 => 164,
+                3 => 318,
+                4 => 518,
+            };
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn exponential() {
+            let actual_histogram = histogram(&mut RankSelection::exponential(0.5), &population());
+
+            let expected_histogram = maplit::btreemap! {
+                1 => 60,
+                2 => 142,
+                3 => 247,
+                4 => 551,
+            };
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn is_not_dominated_by_outliers() {
+            let mut population = population();
+            population[2] = TestIndividual::new(30.0);
+
+            let actual_histogram = histogram(&mut RankSelection::linear(2.0), &population);
+
+            let expected_histogram = maplit::btreemap! {
+                // same as if the outlier had fitness of 4
+                2 => 164,
+                3 => 318,
+                30 => 518,
+            };
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        /// Selects a mating pool of 1000 at once, counting how many times
+        /// each individual (by its index) got chosen.
+        fn pool(
+            method: &mut RankSelection,
+            population: &[TestIndividual],
+        ) -> BTreeMap<usize, usize> {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+            method
+                .select_indices(&fitness(population), 1_000, &mut rng)
+                .into_iter()
+                .fold(Default::default(), |mut histogram, idx| {
+                    *histogram.entry(idx).or_default() += 1;
+                    histogram
+                })
+        }
+
+        #[test]
+        fn selects_mating_pool_at_once() {
+            let actual_pool = pool(&mut RankSelection::linear(2.0), &population());
+
+            let expected_pool = maplit::btreemap! {
+                // index => how many times this individual has been chosen
+                // least fit individual (#1) never gets selected
+                0 => 164,
+                2 => 518,
+                3 => 318,
+            };
+
+            assert_eq!(actual_pool, expected_pool);
+        }
+
+        #[test]
+        fn ties_share_their_rank() {
+            let population = vec![TestIndividual::new(0.0); 4];
+
+            for mut method in [RankSelection::linear(2.0), RankSelection::exponential(0.5)] {
+                let actual_pool = pool(&mut method, &population);
+
+                let expected_pool = maplit::btreemap! {
+                    0 => 248,
+                    1 => 253,
+                    2 => 270,
+                    3 => 229,
+                };
+
+                assert_eq!(actual_pool, expected_pool);
+            }
+        }
+    }
+
+    mod stochastic_universal_sampling {
+        use super::*;
+
+        fn pool(population: &[TestIndividual], count: usize) -> BTreeMap<i32, usize> {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+            StochasticUniversalSampling::new()
+                .select_many(population, count, &mut rng)
+                .into_iter()
+                .fold(Default::default(), |mut histogram, individual| {
+                    *histogram.entry(individual.fitness() as _).or_default() += 1;
+
+                    histogram
+                })
+        }
+
+        #[test]
+        fn single_selections() {
+            let actual_histogram =
+                histogram(&mut StochasticUniversalSampling::new(), &population());
+
+            let expected_histogram = maplit::btreemap! {
+                1 => 98,
+                2 => 202,
+                3 => 278,
+                4 => 422,
+            };
+
+            assert_eq!(actual_histogram, expected_histogram);
+        }
+
+        #[test]
+        fn gives_each_individual_its_expected_share() {
+            // Total fitness is 10, so each point of fitness is worth
+            // exactly 100 selections
+            let expected_histogram = maplit::btreemap! {
+                1 => 100,
+                2 => 200,
+                3 => 300,
+                4 => 400,
+            };
+
+            assert_eq!(pool(&population(), 1_000), expected_histogram);
+        }
+
+        #[test]
+        fn shuffles_the_pool() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let population = population();
+
+            let pool: Vec<_> = StochasticUniversalSampling::new()
+                .select_many(&population, 10, &mut rng)
+                .into_iter()
+                .map(|individual| individual.fitness())
+                .collect();
+
+            let mut sorted = pool.clone();
+            sorted.sort_by(f32::total_cmp);
+
+            assert_ne!(pool, sorted);
+        }
+
+        #[test]
+        fn works_for_all_zero_fitness() {
+            let population = vec![TestIndividual::new(0.0); 4];
+
+            assert_eq!(pool(&population, 8), maplit::btreemap! { 0 => 8 });
+        }
+
+        #[test]
+        fn works_for_non_finite_fitness() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut method = StochasticUniversalSampling::new();
+
+            let pool = method.select_indices(&[1.0, f32::INFINITY, 2.0, f32::NAN], 4, &mut rng);
+            assert_eq!(pool, [1; 4]);
+
+            let mut pool = method.select_indices(&[3e38, 3e38, 3e38], 6, &mut rng);
+            pool.sort();
+            assert_eq!(pool, [0, 0, 1, 1, 2, 2]);
+        }
+    }
 }
@@ -0,0 +1,148 @@
+use crate::*;
+
+/// Function applied to a neuron's weighted sum (bias included) before
+/// the value gets passed on to the next layer.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Activation {
+    /// `max(0, x)`; the default, matching what `Neuron` always used.
+    #[default]
+    Relu,
+
+    /// Like [`Activation::Relu`], but negative inputs are multiplied by
+    /// the given slope instead of being flattened to zero.
+    LeakyRelu(f32),
+
+    /// `1 / (1 + e^-x)`, squashes into `(0, 1)`.
+    Sigmoid,
+
+    /// Squashes into `(-1, 1)` - handy for output layers that have to
+    /// say "turn left" as well as "turn right".
+    Tanh,
+
+    /// Passes the value through unchanged.
+    Identity,
+
+    /// User-supplied function; networks over other types than `f32`
+    /// call it through a conversion to (and from) `f32`.
+    Custom(fn(f32) -> f32),
+}
+
+impl Activation {
+    pub fn apply<F: Float>(&self, x: F) -> F {
+        match self {
+            Self::Relu => x.max(F::ZERO),
+            Self::LeakyRelu(slope) => {
+                if x >= F::ZERO {
+                    x
+                } else {
+                    F::from_f32(*slope) * x
+                }
+            }
+            Self::Sigmoid => F::ONE / (F::ONE + (-x).exp()),
+            Self::Tanh => x.tanh(),
+            Self::Identity => x,
+            Self::Custom(f) => F::from_f32(f(x.to_f32())),
+        }
+    }
+
+    /// Returns derivative of this activation at `x`, given `y` (which
+    /// must be equal to `self.apply(x)`; some derivatives are cheaper to
+    /// compute from the output).
+    ///
+    /// Custom functions are differentiated numerically.
+    pub fn derivative<F: Float>(&self, x: F, y: F) -> F {
+        match self {
+            Self::Relu => {
+                if x > F::ZERO {
+                    F::ONE
+                } else {
+                    F::ZERO
+                }
+            }
+            Self::LeakyRelu(slope) => {
+                if x >= F::ZERO {
+                    F::ONE
+                } else {
+                    F::from_f32(*slope)
+                }
+            }
+            Self::Sigmoid => y * (F::ONE - y),
+            Self::Tanh => F::ONE - y * y,
+            Self::Identity => F::ONE,
+            Self::Custom(f) => {
+                const H: f32 = 1e-3;
+                let x = x.to_f32();
+
+                F::from_f32((f(x + H) - f(x - H)) / (2.0 * H))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relu() {
+        approx::assert_relative_eq!(Activation::Relu.apply(-2.0), 0.0);
+        approx::assert_relative_eq!(Activation::Relu.apply(2.0), 2.0);
+    }
+
+    #[test]
+    fn leaky_relu() {
+        approx::assert_relative_eq!(Activation::LeakyRelu(0.1).apply(-2.0_f32), -0.2);
+        approx::assert_relative_eq!(Activation::LeakyRelu(0.1).apply(2.0), 2.0);
+    }
+
+    #[test]
+    fn sigmoid() {
+        approx::assert_relative_eq!(Activation::Sigmoid.apply(0.0), 0.5);
+        approx::assert_relative_eq!(Activation::Sigmoid.apply(2.0_f32), 0.880_797_1);
+    }
+
+    #[test]
+    fn tanh() {
+        approx::assert_relative_eq!(Activation::Tanh.apply(0.0), 0.0);
+        approx::assert_relative_eq!(Activation::Tanh.apply(-1.0_f32), -0.761_594_2);
+    }
+
+    #[test]
+    fn identity() {
+        approx::assert_relative_eq!(Activation::Identity.apply(-3.5), -3.5);
+    }
+
+    #[test]
+    fn custom() {
+        approx::assert_relative_eq!(Activation::Custom(|x| x * x).apply(-3.0), 9.0);
+    }
+
+    #[test]
+    fn f64() {
+        approx::assert_relative_eq!(Activation::Sigmoid.apply(2.0_f64), 0.880_797_077_977_882_3);
+
+        approx::assert_relative_eq!(Activation::Tanh.derivative(0.0_f64, 0.0), 1.0);
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let activations = [
+            Activation::Relu,
+            Activation::LeakyRelu(0.1),
+            Activation::Sigmoid,
+            Activation::Tanh,
+            Activation::Identity,
+            Activation::Custom(|x| x * x),
+        ];
+
+        for activation in activations {
+            for x in [-1.5_f32, -0.3, 0.4, 2.0] {
+                let h = 1e-3;
+                let expected = (activation.apply(x + h) - activation.apply(x - h)) / (2.0 * h);
+                let actual = activation.derivative(x, activation.apply(x));
+
+                approx::assert_relative_eq!(actual, expected, epsilon = 1e-2);
+            }
+        }
+    }
+}
@@ -0,0 +1,608 @@
+use crate::*;
+
+/// Many networks of the same topology (e.g. a whole flock's brains),
+/// evaluated together.
+///
+/// Parameters are stored struct-of-arrays style - for every weight,
+/// values of all of the networks sit next to each other - so that each
+/// step of the propagation is a tight loop over the networks, instead of
+/// one separate (and allocating) propagation per network.
+///
+/// Inputs and outputs are network-major, i.e. first come all values of
+/// the first network, then all values of the second one and so on.
+#[derive(Debug)]
+pub struct NetworkBatch<F = f32> {
+    len: usize,
+    layers: Vec<BatchLayer<F>>,
+}
+
+#[derive(Debug)]
+struct BatchLayer<F> {
+    inputs: usize,
+    outputs: usize,
+
+    /// `outputs x len`
+    biases: Vec<F>,
+
+    /// `outputs x inputs x len`
+    weights: Vec<F>,
+
+    /// `outputs x outputs x len`
+    recurrent_weights: Option<Vec<F>>,
+
+    /// Learning rule of each network
+    plasticity: Option<Vec<Plasticity<F>>>,
+
+    activation: Activation,
+}
+
+/// What recurrent layers of a [`NetworkBatch`] remember from the previous
+/// step (and what its plastic layers have learned) - i.e. [`State`]s of
+/// all of the networks, laid out like the batch.
+#[derive(Clone, Debug)]
+pub struct BatchState<F = f32> {
+    len: usize,
+
+    /// Last outputs of each layer (`outputs x len`); empty for
+    /// non-recurrent ones.
+    layers: Vec<Vec<F>>,
+
+    /// Changes to weights of each layer (`outputs x inputs x len`); empty
+    /// for non-plastic ones.
+    plastic: Vec<Vec<F>>,
+}
+
+impl<F: Float> NetworkBatch<F> {
+    /// Panics if given no networks or networks of different topologies -
+    /// see [`NetworkBatch::try_new()`] for a non-panicking version.
+    pub fn new<'a>(networks: impl IntoIterator<Item = &'a Network<F>>) -> Self {
+        Self::try_new(networks).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new<'a>(
+        networks: impl IntoIterator<Item = &'a Network<F>>,
+    ) -> Result<Self, NetworkError> {
+        let networks: Vec<_> = networks.into_iter().collect();
+
+        let Some(first) = networks.first() else {
+            return Err(NetworkError::EmptyBatch);
+        };
+
+        for (idx, network) in networks.iter().enumerate() {
+            let matches = network.layers.len() == first.layers.len()
+                && network
+                    .layers
+                    .iter()
+                    .zip(&first.layers)
+                    .all(|(a, b)| same_shape(a, b));
+
+            if !matches {
+                return Err(NetworkError::TopologyMismatch { network: idx });
+            }
+        }
+
+        let len = networks.len();
+
+        let layers = (0..first.layers.len())
+            .map(|idx| {
+                let layer = &first.layers[idx];
+                let (inputs, outputs) = (layer.input_size(), layer.output_size());
+
+                let mut biases = vec![F::ZERO; outputs * len];
+                let mut weights = vec![F::ZERO; outputs * inputs * len];
+
+                let mut recurrent_weights = layer
+                    .is_recurrent()
+                    .then(|| vec![F::ZERO; outputs * outputs * len]);
+
+                for (n, network) in networks.iter().enumerate() {
+                    for (o, (bias, neuron_weights, neuron_recurrent_weights)) in
+                        network.layers[idx].neurons().enumerate()
+                    {
+                        biases[o * len + n] = bias;
+
+                        for (i, &weight) in neuron_weights.iter().enumerate() {
+                            weights[(o * inputs + i) * len + n] = weight;
+                        }
+
+                        if let Some(recurrent_weights) = &mut recurrent_weights {
+                            for (i, &weight) in neuron_recurrent_weights.iter().enumerate() {
+                                recurrent_weights[(o * outputs + i) * len + n] = weight;
+                            }
+                        }
+                    }
+                }
+
+                let plasticity = layer.is_plastic().then(|| {
+                    networks
+                        .iter()
+                        .filter_map(|network| network.layers[idx].plasticity())
+                        .collect()
+                });
+
+                BatchLayer {
+                    inputs,
+                    outputs,
+                    biases,
+                    weights,
+                    recurrent_weights,
+                    plasticity,
+                    activation: layer.activation,
+                }
+            })
+            .collect();
+
+        Ok(Self { len, layers })
+    }
+
+    /// Returns number of networks in this batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Always `false`, since batches can't be empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.layers[0].inputs
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.layers[self.layers.len() - 1].outputs
+    }
+
+    /// Panics if given wrong number of inputs (see [`NetworkBatch`] for
+    /// their layout).
+    pub fn propagate(&self, inputs: &[F]) -> Vec<F> {
+        self.propagate_into(inputs, &mut Scratch::default())
+            .to_vec()
+    }
+
+    /// See [`Network::propagate_into()`].
+    pub fn propagate_into<'a>(&self, inputs: &[F], scratch: &'a mut Scratch<F>) -> &'a [F] {
+        self.propagate_with(inputs, None, scratch)
+    }
+
+    /// See [`Network::propagate_recurrent()`].
+    pub fn propagate_recurrent<'a>(
+        &self,
+        inputs: &[F],
+        state: &mut BatchState<F>,
+        scratch: &'a mut Scratch<F>,
+    ) -> &'a [F] {
+        assert_eq!(state.len, self.len);
+        assert_eq!(state.layers.len(), self.layers.len());
+
+        self.propagate_with(inputs, Some(state), scratch)
+    }
+
+    fn propagate_with<'a>(
+        &self,
+        inputs: &[F],
+        mut state: Option<&mut BatchState<F>>,
+        scratch: &'a mut Scratch<F>,
+    ) -> &'a [F] {
+        assert_eq!(inputs.len(), self.len * self.input_size());
+
+        let Scratch { front, back, .. } = scratch;
+        let (mut current, mut next) = (front, back);
+
+        transpose(inputs, self.input_size(), current);
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let previous = state.as_deref().map(|state| state.layers[idx].as_slice());
+            let deltas = state.as_deref().map(|state| state.plastic[idx].as_slice());
+
+            layer.propagate_into(self.len, current, previous, deltas, next);
+
+            if let Some(state) = &mut state {
+                if layer.recurrent_weights.is_some() {
+                    state.layers[idx].copy_from_slice(next);
+                }
+
+                layer.learn(self.len, current, next, &mut state.plastic[idx]);
+            }
+
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        transpose(current, self.len, next);
+
+        next
+    }
+
+    /// Creates a fresh state for [`NetworkBatch::propagate_recurrent()`].
+    pub fn state(&self) -> BatchState<F> {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| {
+                if layer.recurrent_weights.is_some() {
+                    vec![F::ZERO; layer.outputs * self.len]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+
+        let plastic = self
+            .layers
+            .iter()
+            .map(|layer| {
+                if layer.plasticity.is_some() {
+                    vec![F::ZERO; layer.outputs * layer.inputs * self.len]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+
+        BatchState {
+            len: self.len,
+            layers,
+            plastic,
+        }
+    }
+
+    /// Creates a state out of states of the individual networks, in the
+    /// same order as the networks themselves.
+    pub fn state_from<'a>(&self, states: impl IntoIterator<Item = &'a State<F>>) -> BatchState<F> {
+        let mut batch_state = self.state();
+        let mut count = 0;
+
+        for (n, state) in states.into_iter().enumerate() {
+            assert!(n < self.len, "got more states than networks");
+            assert_eq!(state.layers.len(), batch_state.layers.len());
+
+            let layers = batch_state
+                .layers
+                .iter_mut()
+                .chain(&mut batch_state.plastic)
+                .zip(state.layers.iter().chain(&state.plastic));
+
+            for (layer, values) in layers {
+                for (k, &value) in values.iter().enumerate() {
+                    layer[k * self.len + n] = value;
+                }
+            }
+
+            count += 1;
+        }
+
+        assert_eq!(count, self.len, "got less states than networks");
+
+        batch_state
+    }
+}
+
+impl<F: Float> BatchState<F> {
+    /// Copies what the `network`-th network remembers into `state` - e.g.
+    /// to keep that network's own [`State`] up to date.
+    pub fn copy_into(&self, network: usize, state: &mut State<F>) {
+        assert!(network < self.len);
+        assert_eq!(state.layers.len(), self.layers.len());
+
+        let layers = self
+            .layers
+            .iter()
+            .chain(&self.plastic)
+            .zip(state.layers.iter_mut().chain(&mut state.plastic));
+
+        for (layer, values) in layers {
+            for (k, value) in values.iter_mut().enumerate() {
+                *value = layer[k * self.len + network];
+            }
+        }
+    }
+
+    /// Forgets everything, as if the networks have never been run.
+    pub fn reset(&mut self) {
+        for layer in self.layers.iter_mut().chain(&mut self.plastic) {
+            layer.fill(F::ZERO);
+        }
+    }
+}
+
+impl<F: Float> BatchLayer<F> {
+    /// Like [`Layer::propagate_into()`], but with all values laid out
+    /// `neurons x len`.
+    fn propagate_into(
+        &self,
+        len: usize,
+        inputs: &[F],
+        previous: Option<&[F]>,
+        deltas: Option<&[F]>,
+        outputs: &mut Vec<F>,
+    ) {
+        outputs.clear();
+        outputs.resize(self.outputs * len, F::ZERO);
+
+        for (o, sums) in outputs.chunks_exact_mut(len).enumerate() {
+            let weights = &self.weights[o * self.inputs * len..][..self.inputs * len];
+
+            accumulate(sums, inputs, weights, len);
+
+            if let (Some(previous), Some(recurrent_weights)) = (previous, &self.recurrent_weights) {
+                let recurrent_weights =
+                    &recurrent_weights[o * self.outputs * len..][..self.outputs * len];
+
+                accumulate(sums, previous, recurrent_weights, len);
+            }
+
+            if let (Some(deltas), Some(_)) = (deltas, &self.plasticity) {
+                accumulate(
+                    sums,
+                    inputs,
+                    &deltas[o * self.inputs * len..][..self.inputs * len],
+                    len,
+                );
+            }
+
+            for (sum, &bias) in sums.iter_mut().zip(&self.biases[o * len..][..len]) {
+                *sum = self.activation.apply(bias + *sum);
+            }
+        }
+    }
+
+    /// Like [`Layer::learn()`], but with all values laid out like in
+    /// [`BatchLayer::propagate_into()`].
+    fn learn(&self, len: usize, inputs: &[F], outputs: &[F], deltas: &mut [F]) {
+        let Some(plasticity) = &self.plasticity else {
+            return;
+        };
+
+        let mut rows = self
+            .weights
+            .chunks_exact(len)
+            .zip(deltas.chunks_exact_mut(len));
+
+        for outputs in outputs.chunks_exact(len) {
+            for inputs in inputs.chunks_exact(len) {
+                let (weights, deltas) = rows.next().unwrap();
+
+                for ((((delta, &weight), rule), &input), &output) in deltas
+                    .iter_mut()
+                    .zip(weights)
+                    .zip(plasticity)
+                    .zip(inputs)
+                    .zip(outputs)
+                {
+                    rule.update(weight, delta, input, output);
+                }
+            }
+        }
+    }
+}
+
+/// Adds `sum(values[i] * weights[i])` of each network into `sums`.
+fn accumulate<F: Float>(sums: &mut [F], values: &[F], weights: &[F], len: usize) {
+    for (values, weights) in values.chunks_exact(len).zip(weights.chunks_exact(len)) {
+        for ((sum, &value), &weight) in sums.iter_mut().zip(values).zip(weights) {
+            *sum += value * weight;
+        }
+    }
+}
+
+/// Turns `rows x columns` into `columns x rows`, where `rows` is inferred
+/// from the length of `values`.
+fn transpose<F: Float>(values: &[F], columns: usize, out: &mut Vec<F>) {
+    out.clear();
+
+    if columns == 0 {
+        return;
+    }
+
+    let rows = values.len() / columns;
+
+    out.extend((0..columns).flat_map(|c| (0..rows).map(move |r| values[r * columns + c])));
+}
+
+fn same_shape<F: Float>(a: &Layer<F>, b: &Layer<F>) -> bool {
+    let same_activation = match (a.activation, b.activation) {
+        (Activation::LeakyRelu(a), Activation::LeakyRelu(b)) => a == b,
+        (Activation::Custom(a), Activation::Custom(b)) => std::ptr::fn_addr_eq(a, b),
+        (a, b) => std::mem::discriminant(&a) == std::mem::discriminant(&b),
+    };
+
+    a.input_size() == b.input_size()
+        && a.output_size() == b.output_size()
+        && a.is_recurrent() == b.is_recurrent()
+        && a.is_plastic() == b.is_plastic()
+        && same_activation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn layers() -> [LayerTopology; 3] {
+        [
+            LayerTopology::new(5),
+            LayerTopology::new(6).recurrent(),
+            LayerTopology::with_activation(2, Activation::Tanh),
+        ]
+    }
+
+    fn networks(rng: &mut ChaCha8Rng, layers: &[LayerTopology]) -> Vec<Network> {
+        (0..7).map(|_| Network::random(layers, rng)).collect()
+    }
+
+    fn inputs(rng: &mut ChaCha8Rng, len: usize) -> Vec<f32> {
+        (0..len).map(|_| rng.gen_range(0.0..=1.0)).collect()
+    }
+
+    #[test]
+    fn matches_individual_networks() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let convolution = Convolution::new(3).circular();
+
+        let layers = &[
+            LayerTopology::new(5),
+            LayerTopology::new(5).convolutional(convolution),
+            LayerTopology::with_activation(2, Activation::Sigmoid),
+        ];
+
+        let networks = networks(&mut rng, layers);
+        let batch = NetworkBatch::new(&networks);
+        let inputs = inputs(&mut rng, 7 * 5);
+
+        let expected: Vec<_> = networks
+            .iter()
+            .zip(inputs.chunks(5))
+            .flat_map(|(network, inputs)| network.propagate(inputs.to_vec()))
+            .collect();
+
+        approx::assert_relative_eq!(
+            batch.propagate(&inputs).as_slice(),
+            expected.as_slice(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn matches_individual_recurrent_networks() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let networks = networks(&mut rng, &layers());
+        let batch = NetworkBatch::new(&networks);
+
+        let mut states: Vec<_> = networks.iter().map(Network::state).collect();
+        let mut batch_state = batch.state();
+        let mut scratch = Scratch::default();
+        let mut batch_scratch = Scratch::default();
+
+        for _ in 0..10 {
+            let inputs = inputs(&mut rng, 7 * 5);
+
+            let expected: Vec<_> = networks
+                .iter()
+                .zip(&mut states)
+                .zip(inputs.chunks(5))
+                .flat_map(|((network, state), inputs)| {
+                    network
+                        .propagate_recurrent(inputs, state, &mut scratch)
+                        .to_vec()
+                })
+                .collect();
+
+            let actual = batch.propagate_recurrent(&inputs, &mut batch_state, &mut batch_scratch);
+
+            // Sums are added up in a slightly different order, which the
+            // recurrent connections then amplify
+            approx::assert_relative_eq!(actual, expected.as_slice(), max_relative = 1e-4);
+        }
+
+        for (n, expected) in states.iter().enumerate() {
+            let mut actual = networks[n].state();
+            batch_state.copy_into(n, &mut actual);
+
+            approx::assert_relative_eq!(
+                actual.layers[0].as_slice(),
+                expected.layers[0].as_slice(),
+                max_relative = 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn matches_individual_plastic_networks() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let layers = &[
+            LayerTopology::new(5),
+            LayerTopology::with_activation(4, Activation::Tanh)
+                .recurrent()
+                .plastic(),
+            LayerTopology::with_activation(2, Activation::Tanh).plastic(),
+        ];
+
+        let networks = networks(&mut rng, layers);
+        let batch = NetworkBatch::new(&networks);
+
+        let mut states: Vec<_> = networks.iter().map(Network::state).collect();
+        let mut batch_state = batch.state();
+        let mut scratch = Scratch::default();
+        let mut batch_scratch = Scratch::default();
+
+        for _ in 0..5 {
+            let inputs = inputs(&mut rng, 7 * 5);
+
+            let expected: Vec<_> = networks
+                .iter()
+                .zip(&mut states)
+                .zip(inputs.chunks(5))
+                .flat_map(|((network, state), inputs)| {
+                    network
+                        .propagate_recurrent(inputs, state, &mut scratch)
+                        .to_vec()
+                })
+                .collect();
+
+            let actual = batch.propagate_recurrent(&inputs, &mut batch_state, &mut batch_scratch);
+
+            approx::assert_relative_eq!(actual, expected.as_slice(), max_relative = 1e-4);
+        }
+
+        for (n, expected) in states.iter().enumerate() {
+            let mut actual = networks[n].state();
+            batch_state.copy_into(n, &mut actual);
+
+            approx::assert_relative_eq!(
+                actual.plastic[1].as_slice(),
+                expected.plastic[1].as_slice(),
+                max_relative = 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn state_from_round_trips() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let networks = networks(&mut rng, &layers());
+        let batch = NetworkBatch::new(&networks);
+
+        let states: Vec<_> = networks
+            .iter()
+            .map(|network| {
+                let mut state = network.state();
+                network.propagate_recurrent(&[0.5; 5], &mut state, &mut Scratch::default());
+                state
+            })
+            .collect();
+
+        let batch_state = batch.state_from(&states);
+
+        for (n, expected) in states.iter().enumerate() {
+            let mut actual = networks[n].state();
+            batch_state.copy_into(n, &mut actual);
+
+            assert_eq!(actual.layers, expected.layers);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_batch() {
+        assert_eq!(
+            NetworkBatch::<f32>::try_new([]).unwrap_err(),
+            NetworkError::EmptyBatch
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_topologies() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut networks = networks(&mut rng, &layers());
+
+        let mut other = layers();
+        other[2].activation = Activation::Sigmoid;
+        networks[3] = Network::random(&other, &mut rng);
+
+        assert_eq!(
+            NetworkBatch::try_new(&networks).unwrap_err(),
+            NetworkError::TopologyMismatch { network: 3 }
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use crate::*;
+
+/// Largest number of weights a convolutional layer gets expanded into
+/// (see [`Convolution::expanded_size()`]), so that a tiny file can't make
+/// us allocate gigabytes.
+pub(crate) const MAX_EXPANDED_WEIGHTS: usize = 1 << 24;
+
+/// Turns a layer into a 1D convolution (see
+/// [`LayerTopology::convolutional()`]): instead of every neuron having its
+/// own weights for every input, each filter slides a small kernel along
+/// the inputs, so that a pattern learned in one spot gets recognized in
+/// all of them (e.g. "food to my left" in every cell of an eye).
+///
+/// Outputs are laid out filter-by-filter, i.e. first all positions of
+/// the first filter, then all positions of the second one and so on.
+///
+/// In [`Network::weights()`], a convolutional layer contributes each
+/// filter's bias followed by its kernel - i.e. `filters * (1 +
+/// kernel_size)` values, no matter how many inputs it's got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Convolution {
+    pub filters: usize,
+    pub kernel_size: usize,
+    pub stride: usize,
+    pub padding: Padding,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Padding {
+    /// Kernel only visits positions where it fits entirely.
+    #[default]
+    None,
+
+    /// Inputs are treated as a ring (like the eye's cells are), with the
+    /// kernel centered on every `stride`-th input and wrapping around the
+    /// edges.
+    Circular,
+}
+
+impl Convolution {
+    /// Creates a single-filter convolution with a stride of 1 and no
+    /// padding.
+    pub fn new(kernel_size: usize) -> Self {
+        Self {
+            filters: 1,
+            kernel_size,
+            stride: 1,
+            padding: Padding::None,
+        }
+    }
+
+    pub fn with_filters(mut self, filters: usize) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    pub fn circular(mut self) -> Self {
+        self.padding = Padding::Circular;
+        self
+    }
+
+    /// Returns how many neurons a layer with this convolution has got,
+    /// when it follows a layer of `inputs` neurons.
+    pub fn output_size(&self, inputs: usize) -> usize {
+        self.filters * self.positions(inputs)
+    }
+
+    /// Returns how many places each filter gets applied at.
+    pub(crate) fn positions(&self, inputs: usize) -> usize {
+        match self.padding {
+            Padding::None if inputs < self.kernel_size => 0,
+            Padding::None => (inputs - self.kernel_size) / self.stride + 1,
+            Padding::Circular => inputs.div_ceil(self.stride),
+        }
+    }
+
+    /// Returns how many weights a layer with this convolution has got once
+    /// its kernels are expanded into a dense matrix (which is how it's
+    /// propagated), or `None` if that doesn't fit in `usize`.
+    pub(crate) fn expanded_size(&self, inputs: usize) -> Option<usize> {
+        self.filters
+            .checked_mul(self.positions(inputs))?
+            .checked_mul(inputs)
+    }
+
+    pub(crate) fn parameter_count(&self) -> usize {
+        self.filters * (1 + self.kernel_size)
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        self.filters > 0 && self.kernel_size > 0 && self.stride > 0
+    }
+
+    /// Iterates over `(output, input, parameter)` for each kernel weight
+    /// at each position, where `parameter` indexes the layer's part of
+    /// [`Network::weights()`].
+    ///
+    /// With circular padding and kernels wider than the inputs, the same
+    /// `(output, input)` pair can come up more than once.
+    pub(crate) fn taps(self, inputs: usize) -> impl Iterator<Item = (usize, usize, usize)> {
+        let positions = self.positions(inputs);
+
+        (0..self.filters).flat_map(move |filter| {
+            (0..positions).flat_map(move |position| {
+                (0..self.kernel_size).map(move |k| {
+                    let start = position * self.stride;
+
+                    let input = match self.padding {
+                        Padding::None => start + k,
+                        Padding::Circular => {
+                            (start + k + inputs * self.kernel_size - self.kernel_size / 2) % inputs
+                        }
+                    };
+
+                    (
+                        filter * positions + position,
+                        input,
+                        self.bias_of(filter) + 1 + k,
+                    )
+                })
+            })
+        })
+    }
+
+    /// Returns index of given filter's bias within the layer's part of
+    /// [`Network::weights()`].
+    pub(crate) fn bias_of(&self, filter: usize) -> usize {
+        filter * (1 + self.kernel_size)
+    }
+}
+
+impl LayerTopology {
+    /// Makes this layer convolutional (see [`Convolution`]).
+    ///
+    /// Number of neurons must match what the convolution produces - see
+    /// [`Convolution::output_size()`].
+    pub fn convolutional(mut self, convolution: Convolution) -> Self {
+        self.convolution = Some(convolution);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn taps(convolution: Convolution, inputs: usize) -> Vec<(usize, usize, usize)> {
+        convolution.taps(inputs).collect()
+    }
+
+    #[test]
+    fn output_size() {
+        assert_eq!(Convolution::new(3).output_size(9), 7);
+        assert_eq!(Convolution::new(3).with_stride(2).output_size(9), 4);
+        assert_eq!(Convolution::new(3).with_filters(2).output_size(9), 14);
+        assert_eq!(Convolution::new(10).output_size(9), 0);
+        assert_eq!(Convolution::new(3).circular().output_size(9), 9);
+        assert_eq!(
+            Convolution::new(3).circular().with_stride(2).output_size(9),
+            5
+        );
+    }
+
+    #[test]
+    fn taps_without_padding() {
+        let convolution = Convolution::new(2).with_stride(2).with_filters(2);
+
+        assert_eq!(
+            taps(convolution, 5),
+            [
+                (0, 0, 1),
+                (0, 1, 2),
+                (1, 2, 1),
+                (1, 3, 2),
+                (2, 0, 4),
+                (2, 1, 5),
+                (3, 2, 4),
+                (3, 3, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn taps_with_circular_padding() {
+        let convolution = Convolution::new(3).circular();
+
+        assert_eq!(
+            taps(convolution, 4),
+            [
+                (0, 3, 1),
+                (0, 0, 2),
+                (0, 1, 3),
+                (1, 0, 1),
+                (1, 1, 2),
+                (1, 2, 3),
+                (2, 1, 1),
+                (2, 2, 2),
+                (2, 3, 3),
+                (3, 2, 1),
+                (3, 3, 2),
+                (3, 0, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn propagates_through_shared_kernel() {
+        let convolution = Convolution::new(3).circular();
+
+        let layers = &[
+            LayerTopology::new(4),
+            LayerTopology::with_activation(4, Activation::Identity).convolutional(convolution),
+        ];
+
+        // bias, left, center, right
+        let network = Network::from_weights(layers, vec![0.5, 1.0, 10.0, 100.0]);
+
+        approx::assert_relative_eq!(
+            network.propagate(vec![1.0, 2.0, 3.0, 4.0]).as_slice(),
+            [
+                0.5 + 4.0 + 10.0 + 200.0,
+                0.5 + 1.0 + 20.0 + 300.0,
+                0.5 + 2.0 + 30.0 + 400.0,
+                0.5 + 3.0 + 40.0 + 100.0,
+            ]
+            .as_ref()
+        );
+    }
+
+    #[test]
+    fn mixes_with_dense_layers() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let convolution = Convolution::new(3).with_filters(2).with_stride(2);
+
+        let layers = &[
+            LayerTopology::new(9),
+            LayerTopology::new(8).convolutional(convolution),
+            LayerTopology::new(3),
+            LayerTopology::with_activation(2, Activation::Tanh),
+        ];
+
+        let network = Network::random(layers, &mut rng);
+        let weights = network.weights();
+
+        assert_eq!(weights.len(), 2 * (1 + 3) + 3 * (1 + 8) + 2 * (1 + 3));
+        assert_eq!(network.topology()[1].convolution, Some(convolution));
+
+        let restored = Network::from_weights(layers, weights.clone());
+        let inputs: Vec<f32> = (0..9).map(|n| n as f32 / 9.0).collect();
+
+        assert_eq!(restored.weights(), weights);
+
+        approx::assert_relative_eq!(
+            restored.propagate(inputs.clone()).as_slice(),
+            network.propagate(inputs).as_slice()
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_neurons() {
+        let layers = &[
+            LayerTopology::new(9),
+            LayerTopology::new(9).convolutional(Convolution::new(3)),
+        ];
+
+        assert_eq!(
+            Network::try_from_weights(layers, vec![0.0; 4]).unwrap_err(),
+            NetworkError::ConvolutionSizeMismatch {
+                layer: 1,
+                expected: 7,
+                actual: 9,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_convolutions() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for layer in [
+            LayerTopology::new(9)
+                .convolutional(Convolution::new(1))
+                .recurrent(),
+            LayerTopology::new(0).convolutional(Convolution::new(0)),
+            LayerTopology::new(9).convolutional(Convolution::new(1).with_stride(0)),
+        ] {
+            assert_eq!(
+                Network::<f32>::try_random(&[LayerTopology::new(9), layer], &mut rng).unwrap_err(),
+                NetworkError::InvalidConvolution { layer: 1 }
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_convolutions_too_large_to_expand() {
+        let layers = &[
+            LayerTopology::new(4_000_000_000),
+            LayerTopology::new(4_000_000_000).convolutional(Convolution::new(1)),
+        ];
+
+        assert_eq!(
+            Network::try_from_weights(layers, vec![0.0; 2]).unwrap_err(),
+            NetworkError::TooLarge { layer: 1 }
+        );
+    }
+}
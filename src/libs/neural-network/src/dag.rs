@@ -0,0 +1,328 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use crate::*;
+
+/// Network of an arbitrary (acyclic) shape - e.g. a phenotype built
+/// from a NEAT genome, where hidden nodes don't form neat layers.
+#[derive(Debug)]
+pub struct DagNetwork {
+    /// Indices (into `nodes`) of inputs, in the order they're given
+    inputs: Vec<usize>,
+
+    /// Indices (into `nodes`) of outputs, in the order they're returned
+    outputs: Vec<usize>,
+
+    /// Indices of non-input nodes, topologically sorted - i.e. each node
+    /// comes after all of the nodes it depends on
+    order: Vec<usize>,
+
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DagNodeKind {
+    Input,
+    Hidden,
+    Output,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DagNode {
+    pub id: usize,
+    pub kind: DagNodeKind,
+    pub bias: f32,
+
+    /// Ignored for input nodes.
+    pub activation: Activation,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DagConnection {
+    pub from: usize,
+    pub to: usize,
+    pub weight: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DagError {
+    DuplicatedNode {
+        id: usize,
+    },
+    UnknownNode {
+        id: usize,
+    },
+    ConnectionIntoInput {
+        id: usize,
+    },
+
+    /// Connections form a cycle, so there's no order in which the nodes
+    /// could be evaluated.
+    Cycle,
+}
+
+#[derive(Debug)]
+struct Node {
+    bias: f32,
+    activation: Activation,
+
+    /// `(index of the source node, weight)`
+    incoming: Vec<(usize, f32)>,
+}
+
+impl DagNetwork {
+    /// Builds a network out of given nodes and connections.
+    ///
+    /// Connections may appear in any order; node ids don't have to be
+    /// contiguous, they only have to be unique.
+    pub fn new(nodes: &[DagNode], connections: &[DagConnection]) -> Result<Self, DagError> {
+        let mut indices = HashMap::with_capacity(nodes.len());
+
+        for (idx, node) in nodes.iter().enumerate() {
+            if indices.insert(node.id, idx).is_some() {
+                return Err(DagError::DuplicatedNode { id: node.id });
+            }
+        }
+
+        let index_of = |id| {
+            indices
+                .get(&id)
+                .copied()
+                .ok_or(DagError::UnknownNode { id })
+        };
+
+        let mut compiled: Vec<_> = nodes
+            .iter()
+            .map(|node| Node {
+                bias: node.bias,
+                activation: node.activation,
+                incoming: Vec::new(),
+            })
+            .collect();
+
+        let mut outgoing = vec![Vec::new(); nodes.len()];
+
+        for conn in connections {
+            let from = index_of(conn.from)?;
+            let to = index_of(conn.to)?;
+
+            if nodes[to].kind == DagNodeKind::Input {
+                return Err(DagError::ConnectionIntoInput { id: conn.to });
+            }
+
+            compiled[to].incoming.push((from, conn.weight));
+            outgoing[from].push(to);
+        }
+
+        // Kahn's algorithm
+        let mut pending: Vec<_> = compiled.iter().map(|node| node.incoming.len()).collect();
+        let mut ready: Vec<_> = (0..nodes.len()).filter(|&idx| pending[idx] == 0).collect();
+        let mut sorted = Vec::with_capacity(nodes.len());
+
+        while let Some(idx) = ready.pop() {
+            sorted.push(idx);
+
+            for &next in &outgoing[idx] {
+                pending[next] -= 1;
+
+                if pending[next] == 0 {
+                    ready.push(next);
+                }
+            }
+        }
+
+        if sorted.len() != nodes.len() {
+            return Err(DagError::Cycle);
+        }
+
+        let of_kind = |kind| {
+            nodes
+                .iter()
+                .enumerate()
+                .filter(move |(_, node)| node.kind == kind)
+                .map(|(idx, _)| idx)
+        };
+
+        Ok(Self {
+            inputs: of_kind(DagNodeKind::Input).collect(),
+            outputs: of_kind(DagNodeKind::Output).collect(),
+            order: sorted
+                .into_iter()
+                .filter(|&idx| nodes[idx].kind != DagNodeKind::Input)
+                .collect(),
+            nodes: compiled,
+        })
+    }
+
+    pub fn propagate(&self, inputs: &[f32]) -> Vec<f32> {
+        assert_eq!(inputs.len(), self.inputs.len());
+
+        let mut values = vec![0.0; self.nodes.len()];
+
+        for (&idx, &input) in self.inputs.iter().zip(inputs) {
+            values[idx] = input;
+        }
+
+        for &idx in &self.order {
+            let node = &self.nodes[idx];
+
+            let sum = node
+                .incoming
+                .iter()
+                .map(|&(from, weight)| values[from] * weight)
+                .sum::<f32>();
+
+            values[idx] = node.activation.apply(node.bias + sum);
+        }
+
+        self.outputs.iter().map(|&idx| values[idx]).collect()
+    }
+}
+
+impl fmt::Display for DagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicatedNode { id } => write!(f, "node {} is defined more than once", id),
+            Self::UnknownNode { id } => write!(f, "connection refers to unknown node {}", id),
+            Self::ConnectionIntoInput { id } => {
+                write!(f, "connection leads into input node {}", id)
+            }
+            Self::Cycle => write!(f, "connections form a cycle"),
+        }
+    }
+}
+
+impl Error for DagError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: usize, kind: DagNodeKind, bias: f32) -> DagNode {
+        DagNode {
+            id,
+            kind,
+            bias,
+            activation: Activation::Identity,
+        }
+    }
+
+    fn conn(from: usize, to: usize, weight: f32) -> DagConnection {
+        DagConnection { from, to, weight }
+    }
+
+    #[test]
+    fn propagates_through_arbitrary_shape() {
+        // 0 ---------------> 3
+        //  \               /
+        //   --> 10 --> 7 --
+        //      /
+        // 1 ---
+        let nodes = &[
+            node(0, DagNodeKind::Input, 0.0),
+            node(1, DagNodeKind::Input, 0.0),
+            node(3, DagNodeKind::Output, 0.5),
+            node(7, DagNodeKind::Hidden, -1.0),
+            node(10, DagNodeKind::Hidden, 0.0),
+        ];
+
+        let connections = &[
+            conn(7, 3, 2.0),
+            conn(0, 3, 1.0),
+            conn(10, 7, 3.0),
+            conn(0, 10, 1.0),
+            conn(1, 10, -1.0),
+        ];
+
+        let network = DagNetwork::new(nodes, connections).unwrap();
+
+        // n10 = 2.0 - 1.0 = 1.0
+        // n7 = -1.0 + 3.0 * 1.0 = 2.0
+        // n3 = 0.5 + 2.0 + 2.0 * 2.0 = 6.5
+        approx::assert_relative_eq!(network.propagate(&[2.0, 1.0]).as_slice(), [6.5].as_ref());
+    }
+
+    #[test]
+    fn applies_activations() {
+        let nodes = &[
+            node(0, DagNodeKind::Input, 0.0),
+            DagNode {
+                activation: Activation::Relu,
+                ..node(1, DagNodeKind::Output, 0.0)
+            },
+        ];
+
+        let network = DagNetwork::new(nodes, &[conn(0, 1, 1.0)]).unwrap();
+
+        approx::assert_relative_eq!(network.propagate(&[-2.0]).as_slice(), [0.0].as_ref());
+    }
+
+    #[test]
+    fn output_without_connections_yields_its_activated_bias() {
+        let nodes = &[
+            node(0, DagNodeKind::Input, 0.0),
+            node(1, DagNodeKind::Output, 0.25),
+        ];
+
+        let network = DagNetwork::new(nodes, &[]).unwrap();
+
+        approx::assert_relative_eq!(network.propagate(&[5.0]).as_slice(), [0.25].as_ref());
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let nodes = &[
+            node(0, DagNodeKind::Input, 0.0),
+            node(1, DagNodeKind::Hidden, 0.0),
+            node(2, DagNodeKind::Hidden, 0.0),
+            node(3, DagNodeKind::Output, 0.0),
+        ];
+
+        let connections = &[
+            conn(0, 1, 1.0),
+            conn(1, 2, 1.0),
+            conn(2, 1, 1.0),
+            conn(2, 3, 1.0),
+        ];
+
+        assert_eq!(
+            DagNetwork::new(nodes, connections).unwrap_err(),
+            DagError::Cycle
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_nodes() {
+        let nodes = &[node(0, DagNodeKind::Input, 0.0)];
+
+        assert_eq!(
+            DagNetwork::new(nodes, &[conn(0, 5, 1.0)]).unwrap_err(),
+            DagError::UnknownNode { id: 5 }
+        );
+    }
+
+    #[test]
+    fn rejects_duplicated_nodes() {
+        let nodes = &[
+            node(0, DagNodeKind::Input, 0.0),
+            node(0, DagNodeKind::Output, 0.0),
+        ];
+
+        assert_eq!(
+            DagNetwork::new(nodes, &[]).unwrap_err(),
+            DagError::DuplicatedNode { id: 0 }
+        );
+    }
+
+    #[test]
+    fn rejects_connections_into_inputs() {
+        let nodes = &[
+            node(0, DagNodeKind::Input, 0.0),
+            node(1, DagNodeKind::Input, 0.0),
+        ];
+
+        assert_eq!(
+            DagNetwork::new(nodes, &[conn(0, 1, 1.0)]).unwrap_err(),
+            DagError::ConnectionIntoInput { id: 1 }
+        );
+    }
+}
@@ -0,0 +1,515 @@
+use std::{collections::HashSet, fmt::Write};
+
+use crate::*;
+
+/// Names of a network's inputs and outputs, as shown on its diagram
+/// (see [`Network::to_dot()`] and [`Network::to_svg()`]).
+///
+/// Neurons without a label are simply numbered.
+#[derive(Clone, Debug, Default)]
+pub struct Labels {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+/// Color of positive weights and activations
+const POSITIVE: (u8, u8, u8) = (0x21, 0x66, 0xac);
+
+/// Color of negative weights and activations
+const NEGATIVE: (u8, u8, u8) = (0xb2, 0x18, 0x2b);
+
+const LAYER_SPACING: f32 = 160.0;
+const NEURON_SPACING: f32 = 40.0;
+const NEURON_RADIUS: f32 = 12.0;
+
+/// Margin around the neurons; leaves room for input and output labels
+const MARGIN: f32 = 100.0;
+
+/// A single drawn connection; `from` and `to` are `(layer, neuron)`,
+/// with layers numbered like in [`Network::topology()`].
+struct Edge {
+    from: (usize, usize),
+    to: (usize, usize),
+    weight: f32,
+    recurrent: bool,
+}
+
+impl Network {
+    /// Renders this network as a Graphviz graph, one column per layer.
+    ///
+    /// Connections are colored by the sign of their weights (blue for
+    /// positive, red for negative) and get thicker as the weights grow;
+    /// recurrent connections are dashed.
+    ///
+    /// When given a trace, neurons are shaded (and labeled) by what they
+    /// computed.
+    pub fn to_dot(&self, labels: &Labels, trace: Option<&Trace>) -> String {
+        let topology = self.topology();
+        let edges = self.edges();
+        let max_weight = max_abs(edges.iter().map(|edge| edge.weight));
+        let mut dot = String::new();
+
+        dot.push_str("digraph network {\n");
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    splines=line;\n");
+        dot.push_str("    node [shape=circle, style=filled, fontsize=10];\n");
+
+        for (layer, topology) in topology.iter().enumerate() {
+            let values = layer_values(trace, layer);
+            let max_value = max_abs(values.iter().copied());
+
+            dot.push('\n');
+            let _ = writeln!(dot, "    subgraph cluster_{} {{", layer);
+            dot.push_str("        color=none;\n");
+
+            for neuron in 0..topology.neurons {
+                let mut label = self.label(labels, layer, neuron);
+                let mut fill = String::from("#ffffff");
+
+                if let Some(&value) = values.get(neuron) {
+                    if !label.is_empty() {
+                        label.push('\n');
+                    }
+
+                    let _ = write!(label, "{:.2}", value);
+                    fill = shade(value, max_value);
+                }
+
+                let _ = writeln!(
+                    dot,
+                    "        {} [label=\"{}\", fillcolor=\"{}\"];",
+                    node_id(layer, neuron),
+                    escape(&label),
+                    fill
+                );
+            }
+
+            dot.push_str("    }\n");
+        }
+
+        dot.push('\n');
+
+        for edge in &edges {
+            let intensity = edge.weight.abs() / max_weight;
+
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [penwidth={:.2}, color=\"{}{:02x}\"{}];",
+                node_id(edge.from.0, edge.from.1),
+                node_id(edge.to.0, edge.to.1),
+                thickness(intensity),
+                color(edge.weight),
+                opacity(intensity),
+                if edge.recurrent {
+                    ", style=dashed, constraint=false"
+                } else {
+                    ""
+                }
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders this network as a standalone SVG image, styled the same
+    /// way as [`Network::to_dot()`].
+    pub fn to_svg(&self, labels: &Labels, trace: Option<&Trace>) -> String {
+        let topology = self.topology();
+        let edges = self.edges();
+        let max_weight = max_abs(edges.iter().map(|edge| edge.weight));
+
+        let tallest = topology
+            .iter()
+            .map(|layer| layer.neurons)
+            .max()
+            .unwrap_or(0);
+        let width = 2.0 * MARGIN + LAYER_SPACING * (topology.len() - 1) as f32;
+        let height = 2.0 * MARGIN + NEURON_SPACING * tallest.saturating_sub(1) as f32;
+
+        // Centers each layer vertically
+        let position = |(layer, neuron): (usize, usize)| {
+            let offset = (tallest - topology[layer].neurons) as f32 * NEURON_SPACING / 2.0;
+
+            (
+                MARGIN + LAYER_SPACING * layer as f32,
+                MARGIN + offset + NEURON_SPACING * neuron as f32,
+            )
+        };
+
+        let mut svg = String::new();
+
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="10">"#,
+            w = width,
+            h = height,
+        );
+
+        let _ = writeln!(
+            svg,
+            r##"  <rect width="{}" height="{}" fill="#ffffff"/>"##,
+            width, height
+        );
+
+        for edge in &edges {
+            let intensity = edge.weight.abs() / max_weight;
+            let (x1, y1) = position(edge.from);
+            let (x2, y2) = position(edge.to);
+
+            let path = if !edge.recurrent {
+                format!("M {} {} L {} {}", x1, y1, x2, y2)
+            } else if edge.from == edge.to {
+                // Small loop to the right of the neuron
+                format!(
+                    "M {} {} a {r} {r} 0 1 1 0 {}",
+                    x1 + NEURON_RADIUS,
+                    y1 - NEURON_RADIUS / 2.0,
+                    NEURON_RADIUS,
+                    r = NEURON_RADIUS / 2.0,
+                )
+            } else {
+                // Arc bulging to the right of the layer
+                let bulge = NEURON_RADIUS + (y2 - y1).abs() / 2.0;
+
+                format!(
+                    "M {} {} Q {} {} {} {}",
+                    x1,
+                    y1,
+                    x1 + bulge,
+                    (y1 + y2) / 2.0,
+                    x2,
+                    y2
+                )
+            };
+
+            let _ = writeln!(
+                svg,
+                r#"  <path d="{}" fill="none" stroke="{}" stroke-opacity="{:.2}" stroke-width="{:.2}"{}/>"#,
+                path,
+                color(edge.weight),
+                opacity(intensity) as f32 / 255.0,
+                thickness(intensity),
+                if edge.recurrent {
+                    r#" stroke-dasharray="4 2""#
+                } else {
+                    ""
+                }
+            );
+        }
+
+        for (layer, topology) in topology.iter().enumerate() {
+            let values = layer_values(trace, layer);
+            let max_value = max_abs(values.iter().copied());
+
+            for neuron in 0..topology.neurons {
+                let (x, y) = position((layer, neuron));
+
+                let fill = values
+                    .get(neuron)
+                    .map_or_else(|| String::from("#ffffff"), |&value| shade(value, max_value));
+
+                let _ = writeln!(
+                    svg,
+                    r##"  <circle cx="{}" cy="{}" r="{}" fill="{}" stroke="#333333"/>"##,
+                    x, y, NEURON_RADIUS, fill
+                );
+
+                if let Some(value) = values.get(neuron) {
+                    let _ = writeln!(
+                        svg,
+                        r#"  <text x="{}" y="{}" text-anchor="middle" dominant-baseline="central" font-size="7">{:.2}</text>"#,
+                        x, y, value
+                    );
+                }
+
+                let label = self.label(labels, layer, neuron);
+
+                if label.is_empty() {
+                    continue;
+                }
+
+                let (x, anchor) = if layer == 0 {
+                    (x - NEURON_RADIUS - 4.0, "end")
+                } else {
+                    (x + NEURON_RADIUS + 4.0, "start")
+                };
+
+                let _ = writeln!(
+                    svg,
+                    r#"  <text x="{}" y="{}" text-anchor="{}" dominant-baseline="central">{}</text>"#,
+                    x,
+                    y,
+                    anchor,
+                    escape_xml(&label)
+                );
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn edges(&self) -> Vec<Edge> {
+        let mut edges = Vec::new();
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            // Convolutions connect each neuron only to its window
+            let connected = layer.convolution().map(|convolution| {
+                convolution
+                    .taps(layer.input_size())
+                    .map(|(to, from, _)| (to, from))
+                    .collect::<HashSet<_>>()
+            });
+
+            for (to, (_, weights, recurrent_weights)) in layer.neurons().enumerate() {
+                edges.extend(
+                    weights
+                        .iter()
+                        .enumerate()
+                        .filter(|&(from, _)| {
+                            connected
+                                .as_ref()
+                                .is_none_or(|connected| connected.contains(&(to, from)))
+                        })
+                        .map(|(from, &weight)| Edge {
+                            from: (idx, from),
+                            to: (idx + 1, to),
+                            weight,
+                            recurrent: false,
+                        }),
+                );
+
+                edges.extend(
+                    recurrent_weights
+                        .iter()
+                        .enumerate()
+                        .map(|(from, &weight)| Edge {
+                            from: (idx + 1, from),
+                            to: (idx + 1, to),
+                            weight,
+                            recurrent: true,
+                        }),
+                );
+            }
+        }
+
+        edges
+    }
+
+    /// Returns label of given neuron; only inputs and outputs have them.
+    fn label(&self, labels: &Labels, layer: usize, neuron: usize) -> String {
+        let (names, prefix) = if layer == 0 {
+            (&labels.inputs, "in")
+        } else if layer == self.layers.len() {
+            (&labels.outputs, "out")
+        } else {
+            return String::new();
+        };
+
+        names
+            .get(neuron)
+            .cloned()
+            .unwrap_or_else(|| format!("{} {}", prefix, neuron))
+    }
+}
+
+/// Returns values computed by given layer (numbered like in
+/// [`Network::topology()`]), or nothing if there's no trace.
+fn layer_values(trace: Option<&Trace>, layer: usize) -> &[f32] {
+    match (trace, layer) {
+        (None, _) => &[],
+        (Some(trace), 0) => trace.inputs(),
+        (Some(trace), layer) => &trace.layers()[layer - 1].outputs,
+    }
+}
+
+fn node_id(layer: usize, neuron: usize) -> String {
+    format!("l{}n{}", layer, neuron)
+}
+
+/// Returns the largest magnitude among given values, falling back to
+/// one so that it's always safe to divide by.
+fn max_abs(values: impl Iterator<Item = f32>) -> f32 {
+    let max = values.map(f32::abs).fold(0.0, f32::max);
+
+    if max > 0.0 {
+        max
+    } else {
+        1.0
+    }
+}
+
+fn color(value: f32) -> String {
+    let (r, g, b) = if value < 0.0 { NEGATIVE } else { POSITIVE };
+
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Blends white with the value's color, proportionally to how big the
+/// value is.
+fn shade(value: f32, max: f32) -> String {
+    let (r, g, b) = if value < 0.0 { NEGATIVE } else { POSITIVE };
+    let t = (value.abs() / max).clamp(0.0, 1.0);
+    let mix = |c: u8| (255.0 + (c as f32 - 255.0) * t).round() as u8;
+
+    format!("#{:02x}{:02x}{:02x}", mix(r), mix(g), mix(b))
+}
+
+/// `intensity` is the weight's magnitude relative to the largest one.
+fn thickness(intensity: f32) -> f32 {
+    0.5 + 3.5 * intensity
+}
+
+/// `intensity` is the weight's magnitude relative to the largest one.
+fn opacity(intensity: f32) -> u8 {
+    (255.0 * (0.2 + 0.8 * intensity)).round() as u8
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_xml(label: &str) -> String {
+    label
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network() -> Network {
+        let layers = &[
+            LayerTopology::new(2),
+            LayerTopology::new(2).recurrent(),
+            LayerTopology::new(1),
+        ];
+
+        // hidden: [bias, w, w, rw, rw] x 2
+        // output: [bias, w, w]
+        Network::from_weights(
+            layers,
+            vec![
+                0.0, 1.0, -2.0, 0.5, 0.0, //
+                0.0, 0.5, 0.25, 0.0, -0.5, //
+                0.0, 4.0, -1.0,
+            ],
+        )
+    }
+
+    fn labels() -> Labels {
+        Labels {
+            inputs: vec!["left \"eye\"".into()],
+            outputs: vec!["speed".into()],
+        }
+    }
+
+    mod to_dot {
+        use super::*;
+
+        #[test]
+        fn contains_every_neuron_and_connection() {
+            let dot = network().to_dot(&Labels::default(), None);
+
+            assert_eq!(dot.matches("fillcolor").count(), 5);
+            assert_eq!(dot.matches(" -> ").count(), 4 + 4 + 2);
+            assert_eq!(dot.matches("style=dashed").count(), 4);
+        }
+
+        #[test]
+        fn connects_convolutions_only_within_their_windows() {
+            let convolution = Convolution::new(2).with_filters(2);
+
+            let layers = &[
+                LayerTopology::new(4),
+                LayerTopology::new(convolution.output_size(4)).convolutional(convolution),
+            ];
+
+            let network = Network::from_weights(layers, vec![0.0, 1.0, 0.0, 0.0, 0.5, -0.5]);
+            let dot = network.to_dot(&Labels::default(), None);
+
+            assert_eq!(dot.matches(" -> ").count(), 2 * 3 * 2);
+            assert!(dot.contains("l0n2 -> l1n1 "));
+            assert!(!dot.contains("l0n3 -> l1n1 "));
+        }
+
+        #[test]
+        fn uses_given_labels() {
+            let dot = network().to_dot(&labels(), None);
+
+            assert!(dot.contains(r#"l0n0 [label="left \"eye\"""#));
+            assert!(dot.contains(r#"l0n1 [label="in 1""#));
+            assert!(dot.contains(r#"l1n0 [label="""#));
+            assert!(dot.contains(r#"l2n0 [label="speed""#));
+        }
+
+        #[test]
+        fn styles_connections_by_weight() {
+            let dot = network().to_dot(&Labels::default(), None);
+
+            // The strongest weight (4.0) is the thickest and most opaque
+            assert!(dot.contains(r##"l1n0 -> l2n0 [penwidth=4.00, color="#2166acff"]"##));
+
+            // ... while negative ones are red
+            assert!(dot.contains(r##"l0n1 -> l1n0 [penwidth=2.25, color="#b2182b99"]"##));
+        }
+
+        #[test]
+        fn shades_neurons_by_trace() {
+            let network = network();
+            let trace = network.trace(&[1.0, -1.0]);
+            let dot = network.to_dot(&Labels::default(), Some(&trace));
+
+            // hidden = relu([1.0 + 2.0, 0.5 - 0.25]) = [3.0, 0.25]
+            assert!(dot.contains(r##"l1n0 [label="3.00", fillcolor="#2166ac"]"##));
+            assert!(dot.contains(r##"l0n1 [label="in 1\n-1.00", fillcolor="#b2182b"]"##));
+        }
+    }
+
+    mod to_svg {
+        use super::*;
+
+        #[test]
+        fn is_standalone() {
+            let svg = network().to_svg(&labels(), None);
+
+            assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+            assert!(svg.ends_with("</svg>\n"));
+        }
+
+        #[test]
+        fn contains_every_neuron_and_connection() {
+            let svg = network().to_svg(&Labels::default(), None);
+
+            assert_eq!(svg.matches("<circle").count(), 5);
+            assert_eq!(svg.matches("<path").count(), 4 + 4 + 2);
+            assert_eq!(svg.matches("stroke-dasharray").count(), 4);
+        }
+
+        #[test]
+        fn escapes_labels() {
+            let svg = network().to_svg(&labels(), None);
+
+            assert!(svg.contains(">left &quot;eye&quot;</text>"));
+            assert!(svg.contains(">in 1</text>"));
+            assert!(svg.contains(">speed</text>"));
+        }
+
+        #[test]
+        fn shows_trace() {
+            let network = network();
+            let trace = network.trace(&[1.0, -1.0]);
+            let svg = network.to_svg(&Labels::default(), Some(&trace));
+
+            assert!(svg.contains(">3.00</text>"));
+            assert!(svg.contains(r##"fill="#2166ac" stroke="#333333""##));
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use crate::*;
+
+/// Structural edits that keep (or, for removals, approximate) what the
+/// network computes - e.g. for growing a brain without having to evolve
+/// it again from scratch.
+///
+/// Layers are numbered like in [`Network::topology()`], i.e. `0` is the
+/// input layer.
+impl<F: Float> Network<F> {
+    /// Appends a neuron to given hidden layer.
+    ///
+    /// The neuron's incoming weights are drawn from `initializer`, while
+    /// its outgoing ones (including recurrent connections into the other
+    /// neurons of its layer) start at zero - so the network computes
+    /// exactly the same thing until they evolve.
+    pub fn insert_neuron(
+        &mut self,
+        layer: usize,
+        initializer: Initializer,
+        rng: &mut dyn rand::RngCore,
+    ) -> Result<(), NetworkError> {
+        let idx = self.resizable_layer(layer)?;
+        let target = &self.layers[idx];
+
+        let inputs = target.input_size();
+        let outputs = target.output_size() + 1;
+        let recurrent = target.is_recurrent();
+        let fan_in = if recurrent { inputs + outputs } else { inputs };
+
+        let bias = F::from_f32(initializer.bias(rng));
+
+        let mut weights = |count: usize| -> Vec<F> {
+            (0..count)
+                .map(|_| F::from_f32(initializer.weight(fan_in, outputs, rng)))
+                .collect()
+        };
+
+        let (weights, recurrent_weights) = if recurrent {
+            (weights(inputs), weights(outputs))
+        } else {
+            (weights(inputs), Vec::new())
+        };
+
+        self.layers[idx].push_neuron(bias, weights, recurrent_weights);
+        self.layers[idx + 1].push_input();
+
+        Ok(())
+    }
+
+    /// Inserts a hidden layer that passes values through unchanged, so
+    /// that it becomes layer number `at`.
+    ///
+    /// The new layer computes `activation(x)`, so the network keeps its
+    /// behavior exactly for [`Activation::Identity`], and for
+    /// [`Activation::Relu`] as long as the values passing through are
+    /// never negative (e.g. when the layer before uses ReLU or sigmoid).
+    pub fn insert_identity_layer(
+        &mut self,
+        at: usize,
+        activation: Activation,
+    ) -> Result<(), NetworkError> {
+        if at == 0 || at > self.layers.len() {
+            return Err(NetworkError::InvalidLayer { layer: at });
+        }
+
+        let size = self.layers[at - 1].input_size();
+
+        self.layers
+            .insert(at - 1, Layer::identity(size, activation));
+
+        Ok(())
+    }
+
+    /// Removes given neuron from given hidden layer.
+    ///
+    /// Whatever the neuron contributed gets folded into biases of the
+    /// neurons it was connected to, assuming it'd output `activation(bias)`,
+    /// i.e. what it outputs for all-zero inputs. That's exact for neurons
+    /// that ignore their inputs, and a decent approximation for neurons
+    /// that rarely see any (like most of the eye cells).
+    pub fn remove_neuron(&mut self, layer: usize, neuron: usize) -> Result<(), NetworkError> {
+        let idx = self.resizable_layer(layer)?;
+
+        // A layer without neurons would disconnect the network
+        if neuron >= self.layers[idx].output_size() || self.layers[idx].output_size() == 1 {
+            return Err(NetworkError::InvalidNeuron { layer, neuron });
+        }
+
+        let activation = self.layers[idx].activation;
+        let (bias, recurrent_weights) = self.layers[idx].remove_neuron(neuron);
+        let output = activation.apply(bias);
+
+        let shifts: Vec<_> = recurrent_weights
+            .into_iter()
+            .map(|weight| weight * output)
+            .collect();
+
+        self.layers[idx].shift_biases(&shifts);
+
+        let shifts: Vec<_> = self.layers[idx + 1]
+            .remove_input(neuron)
+            .into_iter()
+            .map(|weight| weight * output)
+            .collect();
+
+        self.layers[idx + 1].shift_biases(&shifts);
+
+        Ok(())
+    }
+
+    /// Returns index (into `self.layers`) of given hidden layer, making
+    /// sure it can change its size.
+    fn resizable_layer(&self, layer: usize) -> Result<usize, NetworkError> {
+        if layer == 0 || layer >= self.layers.len() {
+            return Err(NetworkError::InvalidLayer { layer });
+        }
+
+        let idx = layer - 1;
+
+        // Convolutions determine their own size, and the size of their
+        // inputs
+        if self.layers[idx].convolution().is_some() || self.layers[idx + 1].convolution().is_some()
+        {
+            return Err(NetworkError::ConvolutionalLayer { layer });
+        }
+
+        Ok(idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn network(rng: &mut ChaCha8Rng) -> Network {
+        Network::random(
+            &[
+                LayerTopology::new(4),
+                LayerTopology::with_activation(5, Activation::Tanh).recurrent(),
+                LayerTopology::new(3),
+                LayerTopology::with_activation(2, Activation::Tanh),
+            ],
+            rng,
+        )
+    }
+
+    fn inputs(rng: &mut ChaCha8Rng) -> Vec<Vec<f32>> {
+        (0..10)
+            .map(|_| (0..4).map(|_| rng.gen_range(0.0..=1.0)).collect())
+            .collect()
+    }
+
+    /// Runs both networks through the same sequence of inputs.
+    fn assert_same_behavior(actual: &Network, expected: &Network, inputs: &[Vec<f32>]) {
+        let mut actual_state = actual.state();
+        let mut expected_state = expected.state();
+        let mut scratch = Scratch::default();
+
+        for inputs in inputs {
+            let expected = expected
+                .propagate_recurrent(inputs, &mut expected_state, &mut scratch)
+                .to_vec();
+
+            let actual = actual.propagate_recurrent(inputs, &mut actual_state, &mut scratch);
+
+            approx::assert_relative_eq!(actual, expected.as_slice(), epsilon = 1e-6);
+        }
+    }
+
+    fn copy(network: &Network) -> Network {
+        Network::from_weights(&network.topology(), network.weights())
+    }
+
+    #[test]
+    fn inserting_neurons_preserves_behavior() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let original = network(&mut rng);
+        let mut network = copy(&original);
+
+        network
+            .insert_neuron(1, Initializer::default(), &mut rng)
+            .unwrap();
+
+        network.insert_neuron(2, Initializer::He, &mut rng).unwrap();
+
+        let topology = network.topology();
+
+        assert_eq!(topology[1].neurons, 6);
+        assert_eq!(topology[2].neurons, 4);
+        assert_eq!(network.weights().len(), weight_count(&topology));
+
+        // The new neuron actually listens to its inputs
+        assert!(network
+            .neurons(0)
+            .last()
+            .unwrap()
+            .weights
+            .iter()
+            .any(|&w| w != 0.0));
+
+        assert_same_behavior(&network, &original, &inputs(&mut rng));
+        assert_same_behavior(&copy(&network), &original, &inputs(&mut rng));
+    }
+
+    #[test]
+    fn inserting_identity_layers_preserves_behavior() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let original = network(&mut rng);
+        let mut network = copy(&original);
+
+        network
+            .insert_identity_layer(1, Activation::Identity)
+            .unwrap();
+
+        // Follows a ReLU layer, so never sees anything negative
+        network.insert_identity_layer(4, Activation::Relu).unwrap();
+
+        let topology = network.topology();
+
+        assert_eq!(
+            topology
+                .iter()
+                .map(|layer| layer.neurons)
+                .collect::<Vec<_>>(),
+            [4, 4, 5, 3, 3, 2]
+        );
+
+        assert_same_behavior(&network, &original, &inputs(&mut rng));
+        assert_same_behavior(&copy(&network), &original, &inputs(&mut rng));
+    }
+
+    #[test]
+    fn removing_unused_neurons_preserves_behavior() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let original = network(&mut rng);
+        let mut network = copy(&original);
+
+        network
+            .insert_neuron(1, Initializer::default(), &mut rng)
+            .unwrap();
+
+        network.remove_neuron(1, 5).unwrap();
+
+        assert_same_behavior(&network, &original, &inputs(&mut rng));
+    }
+
+    #[test]
+    fn removing_constant_neurons_folds_them_into_biases() {
+        let layers = &[
+            LayerTopology::new(1),
+            LayerTopology::new(2),
+            LayerTopology::with_activation(1, Activation::Identity),
+        ];
+
+        // The second hidden neuron ignores the input and always says 0.5
+        let original = Network::from_weights(
+            layers,
+            vec![
+                0.0, 1.0, //
+                0.5, 0.0, //
+                0.1, 2.0, 4.0,
+            ],
+        );
+
+        let mut network = copy(&original);
+        network.remove_neuron(1, 1).unwrap();
+
+        assert_eq!(network.weights(), [0.0, 1.0, 0.1 + 4.0 * 0.5, 2.0]);
+
+        for input in [0.0, 0.3, 1.0] {
+            approx::assert_relative_eq!(
+                network.propagate(vec![input]).as_slice(),
+                original.propagate(vec![input]).as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_edits() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = network(&mut rng);
+
+        assert_eq!(
+            network.insert_neuron(0, Initializer::default(), &mut rng),
+            Err(NetworkError::InvalidLayer { layer: 0 })
+        );
+
+        assert_eq!(
+            network.insert_neuron(3, Initializer::default(), &mut rng),
+            Err(NetworkError::InvalidLayer { layer: 3 })
+        );
+
+        assert_eq!(
+            network.insert_identity_layer(4, Activation::Identity),
+            Err(NetworkError::InvalidLayer { layer: 4 })
+        );
+
+        assert_eq!(
+            network.remove_neuron(2, 3),
+            Err(NetworkError::InvalidNeuron {
+                layer: 2,
+                neuron: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_resizing_convolutions() {
+        let convolution = Convolution::new(2);
+
+        let layers = &[
+            LayerTopology::new(3),
+            LayerTopology::new(2).convolutional(convolution),
+            LayerTopology::new(3),
+            LayerTopology::new(2).convolutional(convolution),
+        ];
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network: Network = Network::random(layers, &mut rng);
+
+        for layer in [1, 2] {
+            assert_eq!(
+                network.remove_neuron(layer, 0),
+                Err(NetworkError::ConvolutionalLayer { layer })
+            );
+        }
+
+        // ... but identity layers are fine, since they don't change sizes
+        network
+            .insert_identity_layer(2, Activation::Identity)
+            .unwrap();
+    }
+}
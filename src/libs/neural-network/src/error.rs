@@ -0,0 +1,129 @@
+use std::{error::Error, fmt};
+
+/// What can go wrong when building or running a [`Network`] through
+/// its fallible (`try_*`) methods.
+///
+/// [`Network`]: crate::Network
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkError {
+    /// A network needs at least an input and an output layer.
+    NotEnoughLayers {
+        count: usize,
+    },
+
+    WeightCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+
+    InputSizeMismatch {
+        expected: usize,
+        actual: usize,
+    },
+
+    /// Convolution has a zero-sized kernel, stride or filter count, or
+    /// is used on a recurrent or plastic layer (neither of which is
+    /// supported).
+    InvalidConvolution {
+        layer: usize,
+    },
+
+    /// Convolutional layer's neuron count doesn't match what its
+    /// convolution produces (see [`Convolution::output_size()`]).
+    ///
+    /// [`Convolution::output_size()`]: crate::Convolution::output_size
+    ConvolutionSizeMismatch {
+        layer: usize,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// A [`NetworkBatch`] needs at least one network.
+    ///
+    /// [`NetworkBatch`]: crate::NetworkBatch
+    EmptyBatch,
+
+    /// Networks of a [`NetworkBatch`] must share the same topology
+    /// (including activations), but the given one doesn't match the
+    /// first one.
+    ///
+    /// [`NetworkBatch`]: crate::NetworkBatch
+    TopologyMismatch {
+        network: usize,
+    },
+
+    /// Structural edit (see e.g. [`Network::insert_neuron()`]) refers to
+    /// a layer that doesn't exist or can't be edited that way.
+    ///
+    /// [`Network::insert_neuron()`]: crate::Network::insert_neuron
+    InvalidLayer {
+        layer: usize,
+    },
+
+    /// Structural edit would have to resize a convolutional layer (or its
+    /// inputs), whose size is determined by the convolution.
+    ConvolutionalLayer {
+        layer: usize,
+    },
+
+    /// Neuron doesn't exist, or is the last one in its layer.
+    InvalidNeuron {
+        layer: usize,
+        neuron: usize,
+    },
+
+    /// Layer needs more weights than can be addressed - or, for
+    /// convolutional layers, more than we're willing to expand their
+    /// kernels into.
+    TooLarge {
+        layer: usize,
+    },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEnoughLayers { count } => {
+                write!(f, "expected at least 2 layers, got {}", count)
+            }
+            Self::WeightCountMismatch { expected, actual } => write!(
+                f,
+                "topology requires {} weights, but got {}",
+                expected, actual
+            ),
+            Self::InputSizeMismatch { expected, actual } => {
+                write!(f, "network expects {} inputs, but got {}", expected, actual)
+            }
+            Self::InvalidConvolution { layer } => {
+                write!(f, "layer {} has an invalid convolution", layer)
+            }
+            Self::ConvolutionSizeMismatch {
+                layer,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "layer {} is a convolution producing {} outputs, but has {} neurons",
+                layer, expected, actual
+            ),
+            Self::EmptyBatch => write!(f, "batch needs at least one network"),
+            Self::TopologyMismatch { network } => write!(
+                f,
+                "network {} has a different topology than the rest of the batch",
+                network
+            ),
+            Self::InvalidLayer { layer } => write!(f, "layer {} can't be edited", layer),
+            Self::ConvolutionalLayer { layer } => write!(
+                f,
+                "layer {} can't be resized, since it (or the layer after it) is convolutional",
+                layer
+            ),
+            Self::InvalidNeuron { layer, neuron } => {
+                write!(f, "neuron {} of layer {} can't be removed", neuron, layer)
+            }
+            Self::TooLarge { layer } => write!(f, "layer {} is too large", layer),
+        }
+    }
+}
+
+impl Error for NetworkError {}
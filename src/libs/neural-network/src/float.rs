@@ -0,0 +1,122 @@
+use std::{
+    fmt::Debug,
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+/// Scalar type a [`Network`] computes with.
+///
+/// Networks default to `f32`, which is what the simulation uses; `f64`
+/// comes handy for offline analysis, where precision matters more than
+/// speed.
+///
+/// Whatever the type, random networks are generated in `f32` (and then
+/// converted), so that the same seed yields the same network.
+///
+/// # Determinism
+///
+/// Basic arithmetic is IEEE 754 everywhere, and neurons sum their inputs
+/// strictly left-to-right, so the only thing that can make outputs
+/// differ between platforms are transcendental functions (`exp()`,
+/// `tanh()`), which Rust takes from whatever math library the platform
+/// provides.
+///
+/// Enabling the `deterministic` feature replaces them with pure-Rust
+/// implementations from `libm`, making outputs bit-identical on every
+/// platform (e.g. native vs wasm32) - as long as you don't use
+/// [`Activation::Custom`], which is outside of our control.
+///
+/// [`Network`]: crate::Network
+/// [`Activation::Custom`]: crate::Activation::Custom
+pub trait Float:
+    Copy
+    + Debug
+    + Default
+    + PartialEq
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + Sum
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn from_f32(value: f32) -> Self;
+    fn to_f32(self) -> f32;
+
+    fn exp(self) -> Self;
+    fn tanh(self) -> Self;
+    fn max(self, other: Self) -> Self;
+
+    /// Returns `sum(a[i] * b[i])`, skipping whatever the longer slice has
+    /// in excess.
+    ///
+    /// Sums strictly left-to-right, unless the `simd` feature is enabled
+    /// (and `deterministic` isn't).
+    fn dot(a: &[Self], b: &[Self]) -> Self;
+}
+
+macro_rules! impl_float {
+    ($ty:ty, $exp:path, $tanh:path, $simd_dot:path) => {
+        impl Float for $ty {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+
+            fn from_f32(value: f32) -> Self {
+                value as Self
+            }
+
+            fn to_f32(self) -> f32 {
+                self as f32
+            }
+
+            #[cfg(not(feature = "deterministic"))]
+            fn exp(self) -> Self {
+                <$ty>::exp(self)
+            }
+
+            #[cfg(not(feature = "deterministic"))]
+            fn tanh(self) -> Self {
+                <$ty>::tanh(self)
+            }
+
+            #[cfg(feature = "deterministic")]
+            fn exp(self) -> Self {
+                $exp(self)
+            }
+
+            #[cfg(feature = "deterministic")]
+            fn tanh(self) -> Self {
+                $tanh(self)
+            }
+
+            fn max(self, other: Self) -> Self {
+                <$ty>::max(self, other)
+            }
+
+            #[cfg(not(all(feature = "simd", not(feature = "deterministic"))))]
+            fn dot(a: &[Self], b: &[Self]) -> Self {
+                scalar_dot(a, b)
+            }
+
+            #[cfg(all(feature = "simd", not(feature = "deterministic")))]
+            fn dot(a: &[Self], b: &[Self]) -> Self {
+                $simd_dot(a, b)
+            }
+        }
+    };
+}
+
+impl_float!(f32, libm::expf, libm::tanhf, crate::simd::dot_f32);
+impl_float!(f64, libm::exp, libm::tanh, crate::simd::dot_f64);
+
+pub(crate) fn scalar_dot<F: Float>(a: &[F], b: &[F]) -> F {
+    a.iter().zip(b).map(|(&a, &b)| a * b).sum::<F>()
+}
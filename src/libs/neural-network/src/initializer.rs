@@ -0,0 +1,203 @@
+use rand::{Rng, RngCore};
+use rand_distr::{Distribution, Normal};
+
+/// Strategy for picking the initial weights of [`Network::random()`].
+///
+/// All the strategies draw their numbers from the given RNG only, so the
+/// same seed always yields the same network.
+///
+/// [`Network::random()`]: crate::Network::random
+#[derive(Clone, Copy, Debug)]
+pub enum Initializer {
+    /// Biases and weights drawn uniformly from `low..=high`.
+    Uniform { low: f32, high: f32 },
+
+    /// Xavier/Glorot uniform: weights drawn from `-limit..=limit`, where
+    /// `limit = sqrt(6 / (fan_in + fan_out))`; biases start at zero.
+    ///
+    /// Suited for tanh and sigmoid layers.
+    Xavier,
+
+    /// He normal: weights drawn from `N(0, sqrt(2 / fan_in))`; biases
+    /// start at zero.
+    ///
+    /// Suited for (leaky) ReLU layers.
+    He,
+
+    /// Biases and weights drawn from `N(mean, std_dev)`.
+    Normal { mean: f32, std_dev: f32 },
+
+    /// Everything starts at zero - mostly useful for tests and for
+    /// networks whose weights are about to be trained anyway.
+    Zeros,
+}
+
+impl Initializer {
+    pub(crate) fn bias(&self, rng: &mut dyn RngCore) -> f32 {
+        match *self {
+            Self::Uniform { low, high } => rng.gen_range(low..=high),
+            Self::Normal { mean, std_dev } => normal(mean, std_dev, rng),
+            Self::Xavier | Self::He | Self::Zeros => 0.0,
+        }
+    }
+
+    /// Returns a weight for a neuron of a layer with given number of
+    /// inputs (`fan_in`) and outputs (`fan_out`).
+    pub(crate) fn weight(&self, fan_in: usize, fan_out: usize, rng: &mut dyn RngCore) -> f32 {
+        match *self {
+            Self::Uniform { low, high } => rng.gen_range(low..=high),
+
+            Self::Xavier => {
+                let limit = (6.0 / (fan_in + fan_out).max(1) as f32).sqrt();
+                rng.gen_range(-limit..=limit)
+            }
+
+            Self::He => normal(0.0, (2.0 / fan_in.max(1) as f32).sqrt(), rng),
+            Self::Normal { mean, std_dev } => normal(mean, std_dev, rng),
+            Self::Zeros => 0.0,
+        }
+    }
+}
+
+impl Default for Initializer {
+    /// Uniform `-1.0..=1.0`, which is what networks have always been
+    /// initialized with.
+    fn default() -> Self {
+        Self::Uniform {
+            low: -1.0,
+            high: 1.0,
+        }
+    }
+}
+
+fn normal(mean: f32, std_dev: f32, rng: &mut dyn RngCore) -> f32 {
+    Normal::new(mean, std_dev)
+        .expect("standard deviation must be finite and non-negative")
+        .sample(rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn network(initializer: Initializer, seed: u8) -> Network {
+        let mut rng = ChaCha8Rng::from_seed([seed; 32]);
+
+        Network::random_with(
+            &[LayerTopology::new(50), LayerTopology::new(100)],
+            initializer,
+            &mut rng,
+        )
+    }
+
+    fn std_dev(values: &[f32]) -> f32 {
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+
+        variance.sqrt()
+    }
+
+    /// Splits `Network::weights()` of a single-layer network into
+    /// `(biases, weights)`.
+    fn split(network: &Network) -> (Vec<f32>, Vec<f32>) {
+        let (biases, weights): (Vec<_>, Vec<_>) = network
+            .weights()
+            .into_iter()
+            .enumerate()
+            .partition(|(idx, _)| idx % 51 == 0);
+
+        (
+            biases.into_iter().map(|(_, v)| v).collect(),
+            weights.into_iter().map(|(_, v)| v).collect(),
+        )
+    }
+
+    #[test]
+    fn default_matches_network_random() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let layers = &[LayerTopology::new(3), LayerTopology::new(2)];
+        let expected: Vec<f32> = Network::random(layers, &mut rng).weights();
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let actual: Vec<f32> =
+            Network::random_with(layers, Initializer::default(), &mut rng).weights();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn is_deterministic() {
+        for initializer in [
+            Initializer::default(),
+            Initializer::Xavier,
+            Initializer::He,
+            Initializer::Normal {
+                mean: 0.5,
+                std_dev: 2.0,
+            },
+        ] {
+            assert_eq!(
+                network(initializer, 1).weights(),
+                network(initializer, 1).weights()
+            );
+
+            assert_ne!(
+                network(initializer, 1).weights(),
+                network(initializer, 2).weights()
+            );
+        }
+    }
+
+    #[test]
+    fn uniform() {
+        let initializer = Initializer::Uniform {
+            low: 0.25,
+            high: 0.5,
+        };
+
+        let weights = network(initializer, 0).weights();
+
+        assert!(weights.iter().all(|w| (0.25..=0.5).contains(w)));
+    }
+
+    #[test]
+    fn xavier() {
+        let (biases, weights) = split(&network(Initializer::Xavier, 0));
+        let limit = (6.0f32 / 150.0).sqrt();
+
+        assert!(biases.iter().all(|&b| b == 0.0));
+        assert!(weights.iter().all(|w| (-limit..=limit).contains(w)));
+        assert!(weights.iter().any(|w| w.abs() > limit * 0.9));
+    }
+
+    #[test]
+    fn he() {
+        let (biases, weights) = split(&network(Initializer::He, 0));
+
+        assert!(biases.iter().all(|&b| b == 0.0));
+        approx::assert_relative_eq!(std_dev(&weights), (2.0f32 / 50.0).sqrt(), epsilon = 0.01);
+    }
+
+    #[test]
+    fn normal() {
+        let initializer = Initializer::Normal {
+            mean: 3.0,
+            std_dev: 0.5,
+        };
+
+        let weights = network(initializer, 0).weights();
+        let mean = weights.iter().sum::<f32>() / weights.len() as f32;
+
+        approx::assert_relative_eq!(mean, 3.0, epsilon = 0.05);
+        approx::assert_relative_eq!(std_dev(&weights), 0.5, epsilon = 0.05);
+    }
+
+    #[test]
+    fn zeros() {
+        let weights = network(Initializer::Zeros, 0).weights();
+
+        assert!(weights.iter().all(|&w| w == 0.0));
+    }
+}
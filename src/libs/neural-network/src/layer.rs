@@ -0,0 +1,513 @@
+use crate::*;
+
+/// A dense layer, stored as a single row-major `outputs x inputs`
+/// weight matrix plus a bias vector.
+///
+/// Row `n` of the matrix holds the weights of the layer's `n`-th neuron,
+/// which keeps [`Network::weights()`] in the same `bias, weights...`
+/// per-neuron order it's always had.
+///
+/// Recurrent layers additionally keep an `outputs x outputs` matrix that
+/// feeds the layer's previous outputs back into it (Elman-style); its
+/// rows follow the regular weights of each neuron.
+///
+/// Convolutional layers keep their filters around and expand them into
+/// the regular matrix (with zeros for inputs outside of each neuron's
+/// window), so that propagating doesn't have to care about them.
+///
+/// Plastic layers carry their learning rule's parameters after all of
+/// the neurons; what the rule learns lives in [`State`], not here.
+#[derive(Debug)]
+pub(crate) struct Layer<F = f32> {
+    inputs: usize,
+    biases: Vec<F>,
+    weights: Vec<F>,
+    recurrent_weights: Option<Vec<F>>,
+    kernels: Option<Kernels<F>>,
+    plasticity: Option<Plasticity<F>>,
+    pub(crate) activation: Activation,
+}
+
+#[derive(Debug)]
+struct Kernels<F> {
+    convolution: Convolution,
+
+    /// Bias and kernel of each filter, in the same order as
+    /// `Network::weights()` returns them
+    parameters: Vec<F>,
+}
+
+impl<F: Float> Layer<F> {
+    pub(crate) fn random(
+        input_neurons: usize,
+        topology: LayerTopology,
+        initializer: Initializer,
+        rng: &mut dyn rand::RngCore,
+    ) -> Self {
+        let output_neurons = topology.neurons;
+
+        if let Some(convolution) = topology.convolution {
+            let fan_in = convolution.kernel_size;
+            let fan_out = (convolution.filters * fan_in / convolution.stride).max(1);
+            let mut weights = Vec::with_capacity(convolution.parameter_count());
+
+            for _ in 0..convolution.filters {
+                weights.push(F::from_f32(initializer.bias(rng)));
+
+                for _ in 0..fan_in {
+                    weights.push(F::from_f32(initializer.weight(fan_in, fan_out, rng)));
+                }
+            }
+
+            return Self::from_weights(input_neurons, topology, &mut weights.into_iter());
+        }
+
+        // Recurrent connections are inputs too, as far as initialization
+        // is concerned
+        let fan_in = if topology.recurrent {
+            input_neurons + output_neurons
+        } else {
+            input_neurons
+        };
+
+        let mut weights = Vec::with_capacity((1 + fan_in) * output_neurons);
+
+        for _ in 0..output_neurons {
+            weights.push(F::from_f32(initializer.bias(rng)));
+
+            for _ in 0..fan_in {
+                weights.push(F::from_f32(initializer.weight(fan_in, output_neurons, rng)));
+            }
+        }
+
+        if topology.plastic {
+            for _ in 0..RULE_PARAMETERS {
+                weights.push(F::from_f32(initializer.bias(rng)));
+            }
+        }
+
+        Self::from_weights(input_neurons, topology, &mut weights.into_iter())
+    }
+
+    pub(crate) fn from_weights(
+        input_size: usize,
+        topology: LayerTopology,
+        weights: &mut dyn Iterator<Item = F>,
+    ) -> Self {
+        if let Some(convolution) = topology.convolution {
+            let parameters = (0..convolution.parameter_count())
+                .map(|_| weights.next().expect("got not enough weights"))
+                .collect();
+
+            let mut layer = Self {
+                inputs: input_size,
+                biases: vec![F::ZERO; convolution.output_size(input_size)],
+                weights: vec![F::ZERO; input_size * convolution.output_size(input_size)],
+                recurrent_weights: None,
+                kernels: Some(Kernels {
+                    convolution,
+                    parameters,
+                }),
+                plasticity: None,
+                activation: topology.activation,
+            };
+
+            layer.expand_kernels();
+
+            return layer;
+        }
+
+        let output_size = topology.neurons;
+        let mut biases = Vec::with_capacity(output_size);
+        let mut matrix = Vec::with_capacity(input_size * output_size);
+
+        let mut recurrent_weights = topology
+            .recurrent
+            .then(|| Vec::with_capacity(output_size * output_size));
+
+        // Neuron-by-neuron, so that the order matches `Network::weights()`
+        for _ in 0..output_size {
+            biases.push(weights.next().expect("got not enough weights"));
+
+            for _ in 0..input_size {
+                matrix.push(weights.next().expect("got not enough weights"));
+            }
+
+            if let Some(recurrent_weights) = &mut recurrent_weights {
+                for _ in 0..output_size {
+                    recurrent_weights.push(weights.next().expect("got not enough weights"));
+                }
+            }
+        }
+
+        let plasticity = topology.plastic.then(|| Plasticity::from_weights(weights));
+
+        Self {
+            inputs: input_size,
+            biases,
+            weights: matrix,
+            recurrent_weights,
+            kernels: None,
+            plasticity,
+            activation: topology.activation,
+        }
+    }
+
+    /// Recomputes biases and weights of each neuron from the filters.
+    fn expand_kernels(&mut self) {
+        let Some(Kernels {
+            convolution,
+            parameters,
+        }) = &self.kernels
+        else {
+            return;
+        };
+
+        let positions = convolution.positions(self.inputs);
+
+        for (output, bias) in self.biases.iter_mut().enumerate() {
+            *bias = parameters[convolution.bias_of(output / positions)];
+        }
+
+        self.weights.fill(F::ZERO);
+
+        for (output, input, parameter) in convolution.taps(self.inputs) {
+            self.weights[output * self.inputs + input] += parameters[parameter];
+        }
+    }
+
+    pub(crate) fn input_size(&self) -> usize {
+        self.inputs
+    }
+
+    pub(crate) fn output_size(&self) -> usize {
+        self.biases.len()
+    }
+
+    pub(crate) fn is_recurrent(&self) -> bool {
+        self.recurrent_weights.is_some()
+    }
+
+    pub(crate) fn convolution(&self) -> Option<Convolution> {
+        self.kernels.as_ref().map(|kernels| kernels.convolution)
+    }
+
+    pub(crate) fn is_plastic(&self) -> bool {
+        self.plasticity.is_some()
+    }
+
+    pub(crate) fn plasticity(&self) -> Option<Plasticity<F>> {
+        self.plasticity
+    }
+
+    /// Iterates over this layer's part of `Network::weights()`.
+    pub(crate) fn parameters(&self) -> Box<dyn Iterator<Item = F> + '_> {
+        if let Some(kernels) = &self.kernels {
+            return Box::new(kernels.parameters.iter().copied());
+        }
+
+        Box::new(
+            self.neurons()
+                .flat_map(|(bias, weights, recurrent_weights)| {
+                    std::iter::once(bias)
+                        .chain(weights.iter().copied())
+                        .chain(recurrent_weights.iter().copied())
+                })
+                .chain(self.plasticity.iter().flat_map(Plasticity::parameters)),
+        )
+    }
+
+    /// Iterates over `(bias, weights, recurrent weights)` of each neuron;
+    /// recurrent weights are empty for non-recurrent layers.
+    ///
+    /// For convolutional layers, weights are expanded to cover all of the
+    /// inputs (see [`Layer`]).
+    pub(crate) fn neurons(&self) -> impl Iterator<Item = (F, &[F], &[F])> {
+        let outputs = self.output_size();
+
+        (0..outputs).map(move |n| {
+            let weights = &self.weights[n * self.inputs..(n + 1) * self.inputs];
+
+            let recurrent_weights = self
+                .recurrent_weights
+                .as_ref()
+                .map_or(&[][..], |rw| &rw[n * outputs..(n + 1) * outputs]);
+
+            (self.biases[n], weights, recurrent_weights)
+        })
+    }
+
+    /// Writes this layer's outputs into `outputs`, reusing its allocation.
+    ///
+    /// `previous` are this layer's outputs from the previous step; they
+    /// matter only for recurrent layers, and `None` is treated as if the
+    /// layer had never fired before.
+    ///
+    /// Similarly, `deltas` are what plasticity has learned so far (laid
+    /// out like the weights, see [`Layer::learn()`]); `None` means
+    /// nothing.
+    pub(crate) fn propagate_into(
+        &self,
+        inputs: &[F],
+        previous: Option<&[F]>,
+        deltas: Option<&[F]>,
+        outputs: &mut Vec<F>,
+    ) {
+        outputs.clear();
+        outputs.extend(
+            self.sums(inputs, previous, deltas)
+                .map(|sum| self.activation.apply(sum)),
+        );
+    }
+
+    /// Like [`Layer::propagate_into()`], but returns values from both
+    /// before and after the activation.
+    pub(crate) fn trace(
+        &self,
+        inputs: &[F],
+        previous: Option<&[F]>,
+        deltas: Option<&[F]>,
+    ) -> LayerTrace<F> {
+        let sums: Vec<_> = self.sums(inputs, previous, deltas).collect();
+        let outputs = sums.iter().map(|&sum| self.activation.apply(sum)).collect();
+
+        LayerTrace { sums, outputs }
+    }
+
+    /// Returns values of each neuron before the activation.
+    fn sums<'a>(
+        &'a self,
+        inputs: &'a [F],
+        previous: Option<&'a [F]>,
+        deltas: Option<&'a [F]>,
+    ) -> impl Iterator<Item = F> + 'a {
+        // Non-plastic layers don't learn anything, so their deltas are
+        // empty
+        let deltas = deltas.filter(|deltas| !deltas.is_empty());
+
+        self.neurons()
+            .enumerate()
+            .map(move |(n, (bias, weights, recurrent_weights))| {
+                let output = F::dot(inputs, weights);
+
+                let memory = match previous {
+                    Some(previous) => F::dot(previous, recurrent_weights),
+                    None => F::ZERO,
+                };
+
+                let learned = match deltas {
+                    Some(deltas) => F::dot(inputs, &deltas[n * self.inputs..][..self.inputs]),
+                    None => F::ZERO,
+                };
+
+                bias + output + memory + learned
+            })
+    }
+
+    /// Lets this layer's plasticity (if any) update `deltas` - row-major
+    /// `outputs x inputs` changes to the weights - after it's turned
+    /// `inputs` into `outputs`.
+    pub(crate) fn learn(&self, inputs: &[F], outputs: &[F], deltas: &mut [F]) {
+        if let Some(plasticity) = &self.plasticity {
+            plasticity.learn(inputs, outputs, &self.weights, deltas);
+        }
+    }
+
+    /// Number of values this layer contributes to `Network::weights()`.
+    pub(crate) fn parameter_count(&self) -> usize {
+        if let Some(kernels) = &self.kernels {
+            return kernels.parameters.len();
+        }
+
+        let plasticity = if self.is_plastic() {
+            RULE_PARAMETERS
+        } else {
+            0
+        };
+
+        self.biases.len()
+            + self.weights.len()
+            + self.recurrent_weights.as_ref().map_or(0, Vec::len)
+            + plasticity
+    }
+
+    /// Visits every parameter of this layer, in the same order as
+    /// `Network::weights()` returns them.
+    pub(crate) fn update_parameters(&mut self, mut f: impl FnMut(&mut F)) {
+        if let Some(kernels) = &mut self.kernels {
+            kernels.parameters.iter_mut().for_each(f);
+            self.expand_kernels();
+
+            return;
+        }
+
+        let outputs = self.output_size();
+
+        for n in 0..outputs {
+            f(&mut self.biases[n]);
+
+            for weight in &mut self.weights[n * self.inputs..(n + 1) * self.inputs] {
+                f(weight);
+            }
+
+            if let Some(recurrent_weights) = &mut self.recurrent_weights {
+                for weight in &mut recurrent_weights[n * outputs..(n + 1) * outputs] {
+                    f(weight);
+                }
+            }
+        }
+
+        if let Some(plasticity) = &mut self.plasticity {
+            for parameter in plasticity.parameters_mut() {
+                f(parameter);
+            }
+        }
+    }
+
+    /// Creates a layer that passes its inputs through unchanged (as long
+    /// as the activation does so too).
+    pub(crate) fn identity(size: usize, activation: Activation) -> Self {
+        let mut weights = vec![F::ZERO; size * size];
+
+        for n in 0..size {
+            weights[n * size + n] = F::ONE;
+        }
+
+        Self {
+            inputs: size,
+            biases: vec![F::ZERO; size],
+            weights,
+            recurrent_weights: None,
+            kernels: None,
+            plasticity: None,
+            activation,
+        }
+    }
+
+    /// Appends a neuron; its recurrent weights (if the layer is recurrent)
+    /// must include the neuron's connection to itself, while connections
+    /// from it into other neurons start at zero.
+    pub(crate) fn push_neuron(&mut self, bias: F, weights: Vec<F>, recurrent_weights: Vec<F>) {
+        assert!(self.kernels.is_none());
+        assert_eq!(weights.len(), self.inputs);
+
+        let outputs = self.output_size();
+
+        self.biases.push(bias);
+        self.weights.extend(weights);
+
+        if let Some(matrix) = &mut self.recurrent_weights {
+            assert_eq!(recurrent_weights.len(), outputs + 1);
+
+            let mut resized = Vec::with_capacity((outputs + 1) * (outputs + 1));
+
+            for n in 0..outputs {
+                resized.extend_from_slice(&matrix[n * outputs..(n + 1) * outputs]);
+                resized.push(F::ZERO);
+            }
+
+            resized.extend(recurrent_weights);
+            *matrix = resized;
+        }
+    }
+
+    /// Removes given neuron, returning its bias and the recurrent weights
+    /// that connected it to each of the remaining neurons (empty for
+    /// non-recurrent layers).
+    pub(crate) fn remove_neuron(&mut self, neuron: usize) -> (F, Vec<F>) {
+        assert!(self.kernels.is_none());
+
+        let outputs = self.output_size();
+        let bias = self.biases.remove(neuron);
+
+        self.weights
+            .drain(neuron * self.inputs..(neuron + 1) * self.inputs);
+
+        let mut removed = Vec::new();
+
+        if let Some(matrix) = &mut self.recurrent_weights {
+            let mut resized = Vec::with_capacity((outputs - 1) * (outputs - 1));
+
+            for n in (0..outputs).filter(|&n| n != neuron) {
+                let row = &matrix[n * outputs..(n + 1) * outputs];
+
+                removed.push(row[neuron]);
+                resized.extend_from_slice(&row[..neuron]);
+                resized.extend_from_slice(&row[neuron + 1..]);
+            }
+
+            *matrix = resized;
+        }
+
+        (bias, removed)
+    }
+
+    /// Adds an input, connected to all of the neurons with zero weights.
+    pub(crate) fn push_input(&mut self) {
+        assert!(self.kernels.is_none());
+
+        let mut resized = Vec::with_capacity((self.inputs + 1) * self.output_size());
+
+        for n in 0..self.output_size() {
+            resized.extend_from_slice(&self.weights[n * self.inputs..(n + 1) * self.inputs]);
+            resized.push(F::ZERO);
+        }
+
+        self.weights = resized;
+        self.inputs += 1;
+    }
+
+    /// Removes given input, returning the weights it had in each neuron.
+    pub(crate) fn remove_input(&mut self, input: usize) -> Vec<F> {
+        assert!(self.kernels.is_none());
+
+        let mut removed = Vec::with_capacity(self.output_size());
+        let mut resized = Vec::with_capacity((self.inputs - 1) * self.output_size());
+
+        for n in 0..self.output_size() {
+            let row = &self.weights[n * self.inputs..(n + 1) * self.inputs];
+
+            removed.push(row[input]);
+            resized.extend_from_slice(&row[..input]);
+            resized.extend_from_slice(&row[input + 1..]);
+        }
+
+        self.weights = resized;
+        self.inputs -= 1;
+
+        removed
+    }
+
+    /// Adds given values to biases of each neuron.
+    pub(crate) fn shift_biases(&mut self, shifts: &[F]) {
+        assert!(self.kernels.is_none());
+
+        for (bias, &shift) in self.biases.iter_mut().zip(shifts) {
+            *bias += shift;
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn new(biases: Vec<F>, weights: Vec<Vec<F>>) -> Self {
+        assert_eq!(biases.len(), weights.len());
+
+        let inputs = weights.first().map_or(0, Vec::len);
+        assert!(weights.iter().all(|row| row.len() == inputs));
+
+        Self {
+            inputs,
+            biases,
+            weights: weights.into_iter().flatten().collect(),
+            recurrent_weights: None,
+            kernels: None,
+            plasticity: None,
+            activation: Activation::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_activation(mut self, activation: Activation) -> Self {
+        self.activation = activation;
+        self
+    }
+}
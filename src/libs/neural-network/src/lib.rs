@@ -1,147 +1,405 @@
+pub use self::{
+    activation::*, batch::*, convolution::*, dag::*, diagram::*, error::*, float::*,
+    initializer::*, quantized::*, saliency::*, serialization::*, trace::*, training::*,
+};
+
+mod activation;
+mod batch;
+mod convolution;
+mod dag;
+mod diagram;
+mod edit;
+mod error;
+mod float;
+mod initializer;
+mod layer;
+mod plasticity;
+mod quantized;
+mod saliency;
+mod serialization;
+#[cfg(all(feature = "simd", not(feature = "deterministic")))]
+mod simd;
+mod trace;
+mod training;
+
 use std::iter::once;
 
-use rand::Rng;
-#[derive(Debug)]
-pub struct Network {
-    layers: Vec<Layer>,
-}
+use self::{layer::*, plasticity::*};
+
 #[derive(Debug)]
-struct Layer {
-    neurons: Vec<Neuron>,
+pub struct Network<F = f32> {
+    layers: Vec<Layer<F>>,
 }
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct LayerTopology {
     pub neurons: usize,
+
+    /// Activation applied to this layer's neurons; ignored for the
+    /// first (input) layer, since it doesn't compute anything.
+    pub activation: Activation,
+
+    /// Whether this layer gets to see its own outputs from the previous
+    /// step (see [`Network::propagate_recurrent()`]); ignored for the
+    /// first (input) layer.
+    pub recurrent: bool,
+
+    /// Whether this layer shares weights across its inputs (see
+    /// [`Convolution`]); ignored for the first (input) layer.
+    pub convolution: Option<Convolution>,
+
+    /// Whether this layer's weights keep learning while the network runs
+    /// (see [`LayerTopology::plastic()`]); ignored for the first (input)
+    /// layer.
+    pub plastic: bool,
 }
-#[derive(Debug)]
-struct Neuron {
-    bias: f32,
-    weights: Vec<f32>,
+
+/// Reusable buffers for [`Network::propagate_into()`].
+///
+/// Keeping one of those around (e.g. one for the entire simulation)
+/// makes propagating allocation-free, once the buffers have grown to
+/// the size of the network's widest layer.
+#[derive(Clone, Debug, Default)]
+pub struct Scratch<F = f32> {
+    front: Vec<F>,
+    back: Vec<F>,
+
+    /// Layer's inputs after quantization, see [`QuantizedNetwork`]
+    quantized: Vec<u8>,
 }
 
-impl Network {
-    pub fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
-        self.layers
+/// What a network's recurrent layers remember from the previous step,
+/// and what its plastic layers have learned so far.
+///
+/// Networks themselves are immutable, so each bird (or whoever calls
+/// [`Network::propagate_recurrent()`]) keeps its own state.
+#[derive(Clone, Debug)]
+pub struct State<F = f32> {
+    /// Last outputs of each layer; empty for non-recurrent ones.
+    layers: Vec<Vec<F>>,
+
+    /// Changes to weights of each layer (row-major `outputs x inputs`);
+    /// empty for non-plastic ones.
+    plastic: Vec<Vec<F>>,
+}
+
+impl<F: Float> Network<F> {
+    /// Panics if given wrong number of inputs - see
+    /// [`Network::try_propagate()`] for a non-panicking version.
+    pub fn propagate(&self, inputs: Vec<F>) -> Vec<F> {
+        self.propagate_into(&inputs, &mut Scratch::default())
+            .to_vec()
+    }
+
+    pub fn try_propagate(&self, inputs: Vec<F>) -> Result<Vec<F>, NetworkError> {
+        self.check_inputs(&inputs)?;
+
+        Ok(self.propagate(inputs))
+    }
+
+    /// Like [`Network::propagate()`], but writes everything into given
+    /// scratch buffers instead of allocating new ones.
+    ///
+    /// Recurrent layers behave as if it was the very first step, i.e.
+    /// as if they remembered nothing.
+    pub fn propagate_into<'a>(&self, inputs: &[F], scratch: &'a mut Scratch<F>) -> &'a [F] {
+        self.propagate_with(inputs, None, scratch)
+    }
+
+    /// Propagates inputs through the network, feeding recurrent layers
+    /// with their outputs from the previous call (remembered in `state`)
+    /// and then updating it - plastic layers learn here, too.
+    ///
+    /// For networks without recurrent layers, this is the same as
+    /// [`Network::propagate_into()`].
+    pub fn propagate_recurrent<'a>(
+        &self,
+        inputs: &[F],
+        state: &mut State<F>,
+        scratch: &'a mut Scratch<F>,
+    ) -> &'a [F] {
+        assert_eq!(state.layers.len(), self.layers.len());
+
+        self.propagate_with(inputs, Some(state), scratch)
+    }
+
+    fn propagate_with<'a>(
+        &self,
+        inputs: &[F],
+        mut state: Option<&mut State<F>>,
+        scratch: &'a mut Scratch<F>,
+    ) -> &'a [F] {
+        if let Err(err) = self.check_inputs(inputs) {
+            panic!("{}", err);
+        }
+
+        let Scratch { front, back, .. } = scratch;
+        let (mut current, mut next) = (front, back);
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let inputs = if idx == 0 { inputs } else { current.as_slice() };
+            let previous = state.as_deref().map(|state| state.layers[idx].as_slice());
+            let deltas = state.as_deref().map(|state| state.plastic[idx].as_slice());
+
+            layer.propagate_into(inputs, previous, deltas, next);
+
+            if let Some(state) = &mut state {
+                if layer.is_recurrent() {
+                    state.layers[idx].copy_from_slice(next);
+                }
+
+                layer.learn(inputs, next, &mut state.plastic[idx]);
+            }
+
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        current
+    }
+
+    fn check_inputs(&self, inputs: &[F]) -> Result<(), NetworkError> {
+        let expected = self.layers[0].input_size();
+
+        if inputs.len() != expected {
+            return Err(NetworkError::InputSizeMismatch {
+                expected,
+                actual: inputs.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Creates a fresh state for [`Network::propagate_recurrent()`].
+    pub fn state(&self) -> State<F> {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| {
+                if layer.is_recurrent() {
+                    vec![F::ZERO; layer.output_size()]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+
+        let plastic = self
+            .layers
             .iter()
-            .fold(inputs, |inputs, layer| layer.propagate(inputs))
+            .map(|layer| {
+                if layer.is_plastic() {
+                    vec![F::ZERO; layer.input_size() * layer.output_size()]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+
+        State { layers, plastic }
     }
+
+    /// Creates a network with weights drawn uniformly from `-1.0..=1.0`
+    /// (i.e. using [`Initializer::default()`]).
     pub fn random(layers: &[LayerTopology], rng: &mut dyn rand::RngCore) -> Self {
-        assert!(layers.len() > 1);
+        Self::random_with(layers, Initializer::default(), rng)
+    }
+
+    pub fn random_with(
+        layers: &[LayerTopology],
+        initializer: Initializer,
+        rng: &mut dyn rand::RngCore,
+    ) -> Self {
+        Self::try_random_with(layers, initializer, rng).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_random(
+        layers: &[LayerTopology],
+        rng: &mut dyn rand::RngCore,
+    ) -> Result<Self, NetworkError> {
+        Self::try_random_with(layers, Initializer::default(), rng)
+    }
+
+    pub fn try_random_with(
+        layers: &[LayerTopology],
+        initializer: Initializer,
+        rng: &mut dyn rand::RngCore,
+    ) -> Result<Self, NetworkError> {
+        check_layers(layers)?;
 
         let layers = layers
             .windows(2)
-            .map(|layers| Layer::random(layers[0].neurons, layers[1].neurons, rng))
+            .map(|layers| Layer::random(layers[0].neurons, layers[1], initializer, rng))
             .collect();
 
-        Self { layers }
+        Ok(Self { layers })
+    }
+
+    /// Returns the topology this network has been created with.
+    ///
+    /// Since the input layer doesn't compute anything, its activation
+    /// is reported as the default one.
+    pub fn topology(&self) -> Vec<LayerTopology> {
+        once(LayerTopology::new(self.layers[0].input_size()))
+            .chain(self.layers.iter().map(|layer| LayerTopology {
+                neurons: layer.output_size(),
+                activation: layer.activation,
+                recurrent: layer.is_recurrent(),
+                convolution: layer.convolution(),
+                plastic: layer.is_plastic(),
+            }))
+            .collect()
     }
 
-    pub fn weights(&self) -> Vec<f32> {
+    pub fn weights(&self) -> Vec<F> {
         self.layers
             .iter()
-            .flat_map(|layer| layer.neurons.iter())
-            .flat_map(|neuron| once(&neuron.bias).chain(&neuron.weights))
-            .cloned()
+            .flat_map(|layer| layer.parameters())
             .collect()
     }
 
-    pub fn from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = f32>) -> Self {
-        assert!(layers.len() > 1);
+    pub fn from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = F>) -> Self {
+        Self::try_from_weights(layers, weights).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_weights(
+        layers: &[LayerTopology],
+        weights: impl IntoIterator<Item = F>,
+    ) -> Result<Self, NetworkError> {
+        check_layers(layers)?;
+
+        let weights: Vec<_> = weights.into_iter().collect();
+        let expected = weight_count(layers);
+
+        if weights.len() != expected {
+            return Err(NetworkError::WeightCountMismatch {
+                expected,
+                actual: weights.len(),
+            });
+        }
 
         let mut weights = weights.into_iter();
 
         let layers = layers
             .windows(2)
-            .map(|layers| Layer::from_weights(layers[0].neurons, layers[1].neurons, &mut weights))
+            .map(|layers| Layer::from_weights(layers[0].neurons, layers[1], &mut weights))
             .collect();
 
-        if weights.next().is_some() {
-            panic!("got too many weights");
-        }
-
-        Self { layers }
+        Ok(Self { layers })
     }
 
     #[cfg(test)]
-    pub(crate) fn new(layers: Vec<Layer>) -> Self {
+    pub(crate) fn new(layers: Vec<Layer<F>>) -> Self {
         assert!(layers.len() > 1);
 
         Self { layers }
     }
 }
 
-impl Layer {
-    fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
-        self.neurons
-            .iter()
-            .map(|neuron| neuron.propagate(&inputs))
-            .collect()
-    }
+/// Returns how many values `Network::weights()` yields for a network of
+/// given topology.
+pub(crate) fn weight_count(layers: &[LayerTopology]) -> usize {
+    try_weight_count(layers).unwrap_or_else(|err| panic!("{}", err))
+}
 
-    pub(crate) fn random(
-        input_neurons: usize,
-        output_neurons: usize,
-        rng: &mut dyn rand::RngCore,
-    ) -> Self {
-        let neurons = (0..output_neurons)
-            .map(|_| Neuron::random(input_neurons, rng))
-            .collect();
+/// Like [`weight_count()`], but fails instead of overflowing.
+fn try_weight_count(layers: &[LayerTopology]) -> Result<usize, NetworkError> {
+    layers
+        .windows(2)
+        .enumerate()
+        .try_fold(0usize, |count, (idx, layers)| {
+            layer_weight_count(layers[0].neurons, layers[1])
+                .and_then(|layer_count| count.checked_add(layer_count))
+                .ok_or(NetworkError::TooLarge { layer: idx + 1 })
+        })
+}
 
-        Self { neurons }
+/// Returns how many values given layer contributes to
+/// `Network::weights()`, or `None` if that doesn't fit in `usize`.
+fn layer_weight_count(inputs: usize, layer: LayerTopology) -> Option<usize> {
+    if let Some(convolution) = layer.convolution {
+        return convolution
+            .kernel_size
+            .checked_add(1)?
+            .checked_mul(convolution.filters);
     }
 
-    pub(crate) fn from_weights(
-        input_size: usize,
-        output_size: usize,
-        weights: &mut dyn Iterator<Item = f32>,
-    ) -> Layer {
-        let neurons = (0..output_size)
-            .map(|_| Neuron::from_weights(input_size, weights))
-            .collect();
+    let recurrent_neurons = if layer.recurrent { layer.neurons } else { 0 };
+    let plasticity = if layer.plastic { RULE_PARAMETERS } else { 0 };
 
-        Self { neurons }
-    }
+    inputs
+        .checked_add(1 + recurrent_neurons)?
+        .checked_mul(layer.neurons)?
+        .checked_add(plasticity)
+}
 
-    #[cfg(test)]
-    pub(crate) fn new(neurons: Vec<Neuron>) -> Self {
-        Self { neurons }
+fn check_layers(layers: &[LayerTopology]) -> Result<(), NetworkError> {
+    if layers.len() < 2 {
+        return Err(NetworkError::NotEnoughLayers {
+            count: layers.len(),
+        });
     }
-}
 
-impl Neuron {
-    fn propagate(&self, inputs: &[f32]) -> f32 {
-        let output = inputs
-            .iter()
-            .zip(&self.weights)
-            .map(|(input, weight)| input * weight)
-            .sum::<f32>();
+    for (idx, layers) in layers.windows(2).enumerate() {
+        let Some(convolution) = layers[1].convolution else {
+            continue;
+        };
 
-        (self.bias + output).max(0.0)
-    }
+        if !convolution.is_valid() || layers[1].recurrent || layers[1].plastic {
+            return Err(NetworkError::InvalidConvolution { layer: idx + 1 });
+        }
 
-    fn random(output_size: usize, rng: &mut dyn rand::RngCore) -> Self {
-        let bias = rng.gen_range(-1.0..=1.0);
+        let fits = convolution
+            .expanded_size(layers[0].neurons)
+            .is_some_and(|size| size <= MAX_EXPANDED_WEIGHTS);
 
-        let weights = (0..output_size)
-            .map(|_| rng.gen_range(-1.0..=1.0))
-            .collect();
+        if !fits {
+            return Err(NetworkError::TooLarge { layer: idx + 1 });
+        }
 
-        Self { bias, weights }
+        let expected = convolution.output_size(layers[0].neurons);
+
+        if layers[1].neurons != expected {
+            return Err(NetworkError::ConvolutionSizeMismatch {
+                layer: idx + 1,
+                expected,
+                actual: layers[1].neurons,
+            });
+        }
     }
 
-    pub fn from_weights(output_neurons: usize, weights: &mut dyn Iterator<Item = f32>) -> Self {
-        let bias = weights.next().expect("got not enough weights");
+    try_weight_count(layers)?;
 
-        let weights = (0..output_neurons)
-            .map(|_| weights.next().expect("got not enough weights"))
-            .collect();
+    Ok(())
+}
 
-        Self { bias, weights }
+impl LayerTopology {
+    pub fn new(neurons: usize) -> Self {
+        Self::with_activation(neurons, Activation::default())
     }
 
-    #[cfg(test)]
-    pub(crate) fn new(bias: f32, weights: Vec<f32>) -> Self {
-        Self { bias, weights }
+    pub fn with_activation(neurons: usize, activation: Activation) -> Self {
+        Self {
+            neurons,
+            activation,
+            recurrent: false,
+            convolution: None,
+            plastic: false,
+        }
+    }
+
+    /// Makes this layer recurrent (see [`LayerTopology::recurrent`]).
+    pub fn recurrent(mut self) -> Self {
+        self.recurrent = true;
+        self
+    }
+}
+
+impl<F: Float> State<F> {
+    /// Forgets everything, as if the network has never been run.
+    pub fn reset(&mut self) {
+        for layer in self.layers.iter_mut().chain(&mut self.plastic) {
+            layer.fill(F::ZERO);
+        }
     }
 }
 
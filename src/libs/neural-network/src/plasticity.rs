@@ -0,0 +1,256 @@
+use crate::*;
+
+/// Number of values each plastic layer adds to `Network::weights()`.
+pub(crate) const RULE_PARAMETERS: usize = 5;
+
+/// Largest absolute value a plastic weight (i.e. the evolved weight plus
+/// whatever the layer has learned on top of it) can reach.
+pub(crate) const MAX_PLASTIC_WEIGHT: f32 = 10.0;
+
+/// Hebbian "ABCD" rule a plastic layer (see [`LayerTopology::plastic()`])
+/// updates its weights with, after each step:
+///
+/// ```text
+/// weight += rate * (a * input * output + b * input + c * output + d)
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Plasticity<F> {
+    pub(crate) rate: F,
+    pub(crate) a: F,
+    pub(crate) b: F,
+    pub(crate) c: F,
+    pub(crate) d: F,
+}
+
+impl<F: Float> Plasticity<F> {
+    pub(crate) fn from_weights(weights: &mut dyn Iterator<Item = F>) -> Self {
+        let mut next = || weights.next().expect("got not enough weights");
+
+        Self {
+            rate: next(),
+            a: next(),
+            b: next(),
+            c: next(),
+            d: next(),
+        }
+    }
+
+    /// Returns parameters in the same order as `Network::weights()` does.
+    pub(crate) fn parameters(&self) -> [F; RULE_PARAMETERS] {
+        [self.rate, self.a, self.b, self.c, self.d]
+    }
+
+    pub(crate) fn parameters_mut(&mut self) -> [&mut F; RULE_PARAMETERS] {
+        [
+            &mut self.rate,
+            &mut self.a,
+            &mut self.b,
+            &mut self.c,
+            &mut self.d,
+        ]
+    }
+
+    /// Returns how much the weight between given input and output changes.
+    pub(crate) fn delta(&self, input: F, output: F) -> F {
+        self.rate * (self.a * input * output + self.b * input + self.c * output + self.d)
+    }
+
+    /// Applies the rule to `deltas` (row-major `outputs x inputs`, i.e.
+    /// laid out like the layer's `weights`).
+    pub(crate) fn learn(&self, inputs: &[F], outputs: &[F], weights: &[F], deltas: &mut [F]) {
+        let rows = weights
+            .chunks_exact(inputs.len())
+            .zip(deltas.chunks_exact_mut(inputs.len()));
+
+        for (&output, (weights, deltas)) in outputs.iter().zip(rows) {
+            for ((delta, &weight), &input) in deltas.iter_mut().zip(weights).zip(inputs) {
+                self.update(weight, delta, input, output);
+            }
+        }
+    }
+
+    /// Applies the rule to `delta` learned for given weight, keeping
+    /// `weight + delta` within [`MAX_PLASTIC_WEIGHT`].
+    pub(crate) fn update(&self, weight: F, delta: &mut F, input: F, output: F) {
+        let bound = F::from_f32(MAX_PLASTIC_WEIGHT);
+        let learned = weight + *delta + self.delta(input, output);
+
+        // `Float` has got no `min()`, hence the double negation
+        let learned = -(-learned.max(-bound)).max(-bound);
+
+        *delta = learned - weight;
+    }
+}
+
+impl LayerTopology {
+    /// Makes this layer plastic: its weights keep changing while the
+    /// network runs, following a Hebbian rule:
+    ///
+    /// ```text
+    /// weight += rate * (a * input * output + b * input + c * output + d)
+    /// ```
+    ///
+    /// Rule's parameters are evolvable, just like the weights themselves -
+    /// [`Network::weights()`] lists `rate, a, b, c, d` right after the
+    /// layer's neurons (random networks draw them like they draw biases).
+    ///
+    /// Only [`Network::propagate_recurrent()`] learns; changes end up in
+    /// the [`State`], so the network itself (and e.g. what
+    /// [`Network::propagate()`] returns) stays as it was. Recurrent
+    /// connections don't change.
+    ///
+    /// Learned weights are kept within `-10.0..=10.0`, so that a rule that
+    /// keeps reinforcing itself can't blow the network up over a long
+    /// lifetime.
+    pub fn plastic(mut self) -> Self {
+        self.plastic = true;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn network(rule: [f32; 5]) -> Network {
+        let layers = &[
+            LayerTopology::new(2),
+            LayerTopology::with_activation(1, Activation::Identity).plastic(),
+        ];
+
+        Network::from_weights(layers, [0.0, 1.0, 0.0].into_iter().chain(rule))
+    }
+
+    #[test]
+    fn rule_parameters_are_weights() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let layers = &[
+            LayerTopology::new(3),
+            LayerTopology::new(4).plastic().recurrent(),
+            LayerTopology::with_activation(2, Activation::Tanh).plastic(),
+        ];
+
+        let network: Network = Network::random(layers, &mut rng);
+        let weights = network.weights();
+
+        assert_eq!(weights.len(), (4 * (1 + 3 + 4) + 5) + (2 * (1 + 4) + 5));
+        assert!(network.topology()[1].plastic);
+        assert_eq!(
+            Network::from_weights(layers, weights.clone()).weights(),
+            weights
+        );
+    }
+
+    #[test]
+    fn learns_only_when_propagating_recurrently() {
+        // weight += 0.5 * (input * output)
+        let network = network([0.5, 1.0, 0.0, 0.0, 0.0]);
+        let mut state = network.state();
+        let mut scratch = Scratch::default();
+
+        let outputs: Vec<_> = (0..3)
+            .map(|_| network.propagate_recurrent(&[1.0, 2.0], &mut state, &mut scratch)[0])
+            .collect();
+
+        // step 1: weights = [1.0, 0.0], output = 1.0
+        // step 2: weights = [1.5, 1.0], output = 3.5
+        // step 3: weights = [3.25, 4.5], output = 12.25
+        approx::assert_relative_eq!(outputs.as_slice(), [1.0, 3.5, 12.25].as_ref());
+
+        // ... while the network itself stays as it was
+        approx::assert_relative_eq!(network.propagate(vec![1.0, 2.0])[0], 1.0);
+
+        state.reset();
+        approx::assert_relative_eq!(
+            network.propagate_recurrent(&[1.0, 2.0], &mut state, &mut scratch)[0],
+            1.0
+        );
+    }
+
+    #[test]
+    fn follows_abcd_rule() {
+        let rule = Plasticity {
+            rate: 0.1,
+            a: 1.0,
+            b: 2.0,
+            c: 3.0,
+            d: 4.0,
+        };
+
+        let mut deltas = vec![0.0; 2];
+        rule.learn(&[0.5, -1.0], &[2.0], &[0.0, 0.0], &mut deltas);
+
+        approx::assert_relative_eq!(
+            deltas.as_slice(),
+            [
+                0.1 * (1.0 + 1.0 + 6.0 + 4.0),
+                0.1 * (-2.0 - 2.0 + 6.0 + 4.0),
+            ]
+            .as_ref()
+        );
+    }
+
+    #[test]
+    fn keeps_learned_weights_bounded() {
+        let rule = Plasticity {
+            rate: 1.0,
+            a: 0.0,
+            b: 0.0,
+            c: 0.0,
+            d: 4.0,
+        };
+
+        let mut deltas = vec![0.0; 2];
+
+        for _ in 0..5 {
+            rule.learn(&[1.0, 1.0], &[1.0], &[3.0, -20.0], &mut deltas);
+        }
+
+        // 3.0 grows up to the bound, while -20.0 first gets pulled into it
+        // (-10.0) and then keeps growing from there (up to 6.0)
+        approx::assert_relative_eq!(deltas.as_slice(), [7.0, 26.0].as_ref());
+    }
+
+    #[test]
+    fn stays_finite_over_a_lifetime() {
+        // weight += 0.5 * (input * output), which keeps reinforcing itself
+        let layers = &[
+            LayerTopology::new(3),
+            LayerTopology::with_activation(3, Activation::Identity).plastic(),
+            LayerTopology::with_activation(2, Activation::Tanh),
+        ];
+
+        let weights = [0.0, 0.5, 0.5, 0.5]
+            .repeat(3)
+            .into_iter()
+            .chain([0.5, 1.0, 0.0, 0.0, 0.0])
+            .chain([0.0, 0.5, 0.5, 0.5].repeat(2));
+
+        let network: Network = Network::from_weights(layers, weights);
+        let mut state = network.state();
+        let mut scratch = Scratch::default();
+
+        for _ in 0..2_500 {
+            let outputs = network.propagate_recurrent(&[1.0, 0.5, 0.25], &mut state, &mut scratch);
+
+            assert!(outputs.iter().all(|output| output.is_finite()));
+        }
+    }
+
+    #[test]
+    fn zero_rate_disables_learning() {
+        let network = network([0.0, 1.0, 1.0, 1.0, 1.0]);
+        let mut state = network.state();
+        let mut scratch = Scratch::default();
+
+        for _ in 0..5 {
+            approx::assert_relative_eq!(
+                network.propagate_recurrent(&[1.0, 2.0], &mut state, &mut scratch)[0],
+                1.0
+            );
+        }
+    }
+}
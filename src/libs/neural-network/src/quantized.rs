@@ -0,0 +1,469 @@
+use crate::*;
+
+/// Int8 version of a [`Network`], for when it only has to be run (e.g.
+/// a population that's done evolving); see [`Network::quantize()`].
+///
+/// Weights of each layer are mapped onto 256 evenly spaced levels,
+/// described by the layer's scale and zero-point:
+///
+/// ```text
+/// weight = scale * (quantized - zero_point)
+/// ```
+///
+/// ... so each weight ends up off by at most half of the scale. Biases
+/// (of which there are few) stay `f32`.
+///
+/// When propagating, each layer's inputs get quantized as well (the same
+/// way, just onto `0..=255`), so that dot products are computed entirely
+/// on integers - accumulated in `i32` (or `i64`, for layers too wide for
+/// `i32` to hold the sum) and rescaled back into `f32` just once per
+/// neuron, before applying the bias and activation.
+///
+/// For brains like the ones we evolve (inputs in `0..=1`, weights in
+/// `-1..=1`), outputs of a single step stay within
+/// [`QuantizedNetwork::TOLERANCE`] of the original network's; note that
+/// recurrent layers can amplify the difference over many steps.
+///
+/// Convolutional layers get quantized (and reported by
+/// [`QuantizedNetwork::topology()`] and [`QuantizedNetwork::weights()`])
+/// as the equivalent dense ones, while plastic layers lose their
+/// plasticity (and the rule's parameters).
+#[derive(Clone, Debug)]
+pub struct QuantizedNetwork {
+    layers: Vec<QuantizedLayer>,
+}
+
+#[derive(Clone, Debug)]
+struct QuantizedLayer {
+    inputs: usize,
+    biases: Vec<f32>,
+
+    /// Row-major `outputs x inputs`, like in [`Layer`]
+    weights: Vec<i8>,
+
+    /// Row-major `outputs x outputs`, like in [`Layer`]
+    recurrent_weights: Option<Vec<i8>>,
+
+    scale: f32,
+    zero_point: i8,
+    activation: Activation,
+}
+
+impl Network {
+    /// Performs post-training quantization of this network (see
+    /// [`QuantizedNetwork`]).
+    pub fn quantize(&self) -> QuantizedNetwork {
+        QuantizedNetwork {
+            layers: self.layers.iter().map(QuantizedLayer::new).collect(),
+        }
+    }
+}
+
+impl QuantizedNetwork {
+    /// Largest difference between outputs of a quantized network and the
+    /// original one (for a single step) that our tests allow for.
+    pub const TOLERANCE: f32 = 0.05;
+
+    pub fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
+        self.propagate_into(&inputs, &mut Scratch::default())
+            .to_vec()
+    }
+
+    /// See [`Network::propagate_into()`].
+    pub fn propagate_into<'a>(&self, inputs: &[f32], scratch: &'a mut Scratch) -> &'a [f32] {
+        self.propagate_with(inputs, None, scratch)
+    }
+
+    /// See [`Network::propagate_recurrent()`].
+    pub fn propagate_recurrent<'a>(
+        &self,
+        inputs: &[f32],
+        state: &mut State,
+        scratch: &'a mut Scratch,
+    ) -> &'a [f32] {
+        assert_eq!(state.layers.len(), self.layers.len());
+
+        self.propagate_with(inputs, Some(state), scratch)
+    }
+
+    fn propagate_with<'a>(
+        &self,
+        inputs: &[f32],
+        mut state: Option<&mut State>,
+        scratch: &'a mut Scratch,
+    ) -> &'a [f32] {
+        assert_eq!(inputs.len(), self.layers[0].inputs);
+
+        let Scratch {
+            front,
+            back,
+            quantized,
+        } = scratch;
+
+        let (mut current, mut next) = (front, back);
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let inputs = if idx == 0 { inputs } else { current.as_slice() };
+            let previous = state.as_deref().map(|state| state.layers[idx].as_slice());
+
+            layer.propagate_into(inputs, previous, quantized, next);
+
+            if let Some(state) = &mut state {
+                if layer.recurrent_weights.is_some() {
+                    state.layers[idx].copy_from_slice(next);
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        current
+    }
+
+    /// Creates a fresh state for [`QuantizedNetwork::propagate_recurrent()`].
+    pub fn state(&self) -> State {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| {
+                if layer.recurrent_weights.is_some() {
+                    vec![0.0; layer.biases.len()]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+
+        let plastic = vec![Vec::new(); self.layers.len()];
+
+        State { layers, plastic }
+    }
+
+    /// Returns the topology of the network this one was quantized from.
+    pub fn topology(&self) -> Vec<LayerTopology> {
+        std::iter::once(LayerTopology::new(self.layers[0].inputs))
+            .chain(self.layers.iter().map(|layer| LayerTopology {
+                neurons: layer.biases.len(),
+                activation: layer.activation,
+                recurrent: layer.recurrent_weights.is_some(),
+                convolution: None,
+                plastic: false,
+            }))
+            .collect()
+    }
+
+    /// Returns weights after quantization, in the same order as
+    /// [`Network::weights()`] - handy for turning this network back into
+    /// a regular one.
+    pub fn weights(&self) -> Vec<f32> {
+        let mut weights = Vec::new();
+
+        for layer in &self.layers {
+            let outputs = layer.biases.len();
+
+            for n in 0..outputs {
+                weights.push(layer.biases[n]);
+
+                weights.extend(
+                    layer.weights[n * layer.inputs..(n + 1) * layer.inputs]
+                        .iter()
+                        .map(|&weight| layer.dequantize(weight)),
+                );
+
+                if let Some(recurrent_weights) = &layer.recurrent_weights {
+                    weights.extend(
+                        recurrent_weights[n * outputs..(n + 1) * outputs]
+                            .iter()
+                            .map(|&weight| layer.dequantize(weight)),
+                    );
+                }
+            }
+        }
+
+        weights
+    }
+}
+
+impl QuantizedLayer {
+    fn new(layer: &Layer) -> Self {
+        let mut biases = Vec::with_capacity(layer.output_size());
+        let mut weights = Vec::with_capacity(layer.input_size() * layer.output_size());
+
+        let mut recurrent_weights = layer
+            .is_recurrent()
+            .then(|| Vec::with_capacity(layer.output_size() * layer.output_size()));
+
+        for (bias, neuron_weights, neuron_recurrent_weights) in layer.neurons() {
+            biases.push(bias);
+            weights.extend_from_slice(neuron_weights);
+
+            if let Some(recurrent_weights) = &mut recurrent_weights {
+                recurrent_weights.extend_from_slice(neuron_recurrent_weights);
+            }
+        }
+
+        // The range always includes zero, so that zero weights stay exact
+        let (min, max) = weights
+            .iter()
+            .chain(recurrent_weights.iter().flatten())
+            .fold((0.0f32, 0.0f32), |(min, max), &weight| {
+                (min.min(weight), max.max(weight))
+            });
+
+        let scale = if max > min { (max - min) / 255.0 } else { 1.0 };
+
+        let zero_point = (-128.0 - min / scale).round().clamp(-128.0, 127.0) as i8;
+
+        let quantize = |weights: Vec<f32>| -> Vec<i8> {
+            weights
+                .into_iter()
+                .map(|weight| {
+                    (weight / scale + zero_point as f32)
+                        .round()
+                        .clamp(-128.0, 127.0) as i8
+                })
+                .collect()
+        };
+
+        Self {
+            inputs: layer.input_size(),
+            biases,
+            weights: quantize(weights),
+            recurrent_weights: recurrent_weights.map(quantize),
+            scale,
+            zero_point,
+            activation: layer.activation,
+        }
+    }
+
+    fn dequantize(&self, weight: i8) -> f32 {
+        self.scale * (weight as i32 - self.zero_point as i32) as f32
+    }
+
+    fn propagate_into(
+        &self,
+        inputs: &[f32],
+        previous: Option<&[f32]>,
+        quantized: &mut Vec<u8>,
+        outputs: &mut Vec<f32>,
+    ) {
+        let neurons = self.biases.len();
+
+        quantized.clear();
+
+        let inputs_range = Range::quantize(inputs, quantized);
+
+        let previous_range = previous
+            .filter(|_| self.recurrent_weights.is_some())
+            .map(|previous| Range::quantize(previous, quantized));
+
+        let (inputs, previous) = quantized.split_at(inputs.len());
+
+        outputs.clear();
+
+        outputs.extend((0..neurons).map(|n| {
+            let weights = &self.weights[n * self.inputs..][..self.inputs];
+            let mut sum = inputs_range.scale * self.dot(inputs, inputs_range, weights) as f32;
+
+            if let (Some(previous_range), Some(recurrent_weights)) =
+                (previous_range, &self.recurrent_weights)
+            {
+                let weights = &recurrent_weights[n * neurons..][..neurons];
+                sum += previous_range.scale * self.dot(previous, previous_range, weights) as f32;
+            }
+
+            self.activation.apply(self.biases[n] + self.scale * sum)
+        }));
+    }
+
+    /// Returns `sum((value - value_zero_point) * (weight - zero_point))`,
+    /// i.e. the dot product without any of the scales (which get applied
+    /// once, by the caller).
+    fn dot(&self, values: &[u8], range: Range, weights: &[i8]) -> i64 {
+        let zero_point = self.zero_point as i32;
+
+        let terms = values.iter().zip(weights).map(|(&value, &weight)| {
+            (value as i32 - range.zero_point) * (weight as i32 - zero_point)
+        });
+
+        // Each term is within `±255 * 255`
+        if values.len() <= (i32::MAX / (255 * 255)) as usize {
+            terms.sum::<i32>() as i64
+        } else {
+            terms.map(|term| term as i64).sum()
+        }
+    }
+}
+
+/// Describes how values got quantized, just like [`QuantizedLayer`]'s
+/// `scale` and `zero_point` describe its weights - except that values are
+/// mapped onto `0..=255`.
+#[derive(Clone, Copy, Debug)]
+struct Range {
+    scale: f32,
+    zero_point: i32,
+}
+
+impl Range {
+    /// Appends quantized `values` to `quantized`, returning how they got
+    /// quantized.
+    fn quantize(values: &[f32], quantized: &mut Vec<u8>) -> Self {
+        // The range always includes zero, so that zero values stay exact
+        let (min, max) = values.iter().fold((0.0f32, 0.0f32), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        });
+
+        let scale = if max > min { (max - min) / 255.0 } else { 1.0 };
+        let zero_point = (-min / scale).round().clamp(0.0, 255.0);
+
+        quantized.extend(
+            values
+                .iter()
+                .map(|value| (value / scale + zero_point).round().clamp(0.0, 255.0) as u8),
+        );
+
+        Self {
+            scale,
+            zero_point: zero_point as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::iter;
+
+    /// Shaped like the simulation's brains
+    fn layers() -> [LayerTopology; 3] {
+        [
+            LayerTopology::new(9),
+            LayerTopology::new(18).recurrent(),
+            LayerTopology::with_activation(2, Activation::Tanh),
+        ]
+    }
+
+    #[test]
+    fn weights_are_off_by_at_most_half_a_step() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network = Network::random(&layers(), &mut rng);
+        let quantized = network.quantize();
+
+        let max_step = quantized
+            .layers
+            .iter()
+            .map(|layer| layer.scale)
+            .fold(0.0, f32::max);
+
+        assert!(max_step < 2.0 / 255.0 + 1e-6);
+
+        for (actual, expected) in quantized.weights().into_iter().zip(network.weights()) {
+            assert!((actual - expected).abs() <= max_step / 2.0 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn zero_weights_stay_exact() {
+        let layers = &[LayerTopology::new(3), LayerTopology::new(1)];
+        let network = Network::from_weights(layers, vec![0.5, 0.0, 0.3, -0.7]);
+
+        assert_eq!(network.quantize().weights()[1], 0.0);
+    }
+
+    #[test]
+    fn handles_all_zero_layers() {
+        let layers = &[LayerTopology::new(2), LayerTopology::new(1)];
+        let network = Network::from_weights(layers, vec![0.25, 0.0, 0.0]);
+
+        assert_eq!(network.quantize().weights(), [0.25, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn preserves_topology() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network = Network::random(&layers(), &mut rng);
+        let quantized = network.quantize();
+
+        assert_eq!(
+            format!("{:?}", quantized.topology()),
+            format!("{:?}", network.topology())
+        );
+
+        assert_eq!(quantized.weights().len(), network.weights().len());
+    }
+
+    #[test]
+    fn outputs_stay_within_tolerance() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for _ in 0..50 {
+            let network = Network::random(&layers(), &mut rng);
+            let quantized = network.quantize();
+
+            for _ in 0..20 {
+                let inputs: Vec<f32> = (0..9).map(|_| rng.gen_range(0.0..=1.0)).collect();
+
+                let expected = network.propagate(inputs.clone());
+                let actual = quantized.propagate(inputs);
+
+                for (actual, expected) in actual.iter().zip(&expected) {
+                    assert!(
+                        (actual - expected).abs() <= QuantizedNetwork::TOLERANCE,
+                        "{} vs {}",
+                        actual,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn handles_layers_too_wide_for_i32() {
+        let layers = &[
+            LayerTopology::new(70_000),
+            LayerTopology::with_activation(1, Activation::Identity),
+        ];
+
+        let weights = [0.0, 1.0].into_iter().chain(iter::repeat_n(-1.0, 69_999));
+        let network = Network::from_weights(layers, weights);
+
+        let inputs: Vec<_> = iter::once(0.0).chain(iter::repeat_n(1.0, 69_999)).collect();
+
+        let expected = network.propagate(inputs.clone())[0];
+        let actual = network.quantize().propagate(inputs)[0];
+
+        assert_eq!(expected, -69_999.0);
+        approx::assert_relative_eq!(actual, expected, max_relative = 1e-2);
+    }
+
+    #[test]
+    fn recurrent_layers_behave_like_dequantized_network() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let quantized = Network::random(&layers(), &mut rng).quantize();
+        let dequantized = Network::from_weights(&quantized.topology(), quantized.weights());
+
+        let mut state = dequantized.state();
+        let mut quantized_state = quantized.state();
+        let mut scratch = Scratch::default();
+
+        for _ in 0..10 {
+            let inputs: Vec<f32> = (0..9).map(|_| rng.gen_range(0.0..=1.0)).collect();
+
+            let expected = dequantized
+                .propagate_recurrent(&inputs, &mut state, &mut scratch)
+                .to_vec();
+
+            let actual = quantized.propagate_recurrent(&inputs, &mut quantized_state, &mut scratch);
+
+            // Not exact, since values get quantized as well
+            approx::assert_relative_eq!(
+                actual,
+                expected.as_slice(),
+                epsilon = QuantizedNetwork::TOLERANCE
+            );
+        }
+    }
+}
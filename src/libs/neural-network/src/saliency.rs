@@ -0,0 +1,380 @@
+use crate::*;
+
+/// How [`Network::saliency()`] computes derivatives.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum SaliencyMethod {
+    /// Nudges each input by `step` in both directions and looks at how
+    /// outputs change; slow, but doesn't care about what's inside.
+    FiniteDifferences { step: f32 },
+
+    /// Backpropagates through the network, using derivatives of the
+    /// activations (see [`Activation::derivative()`] - custom ones are
+    /// differentiated numerically).
+    #[default]
+    Analytic,
+}
+
+/// How much each output of a network reacts to each of its inputs, i.e.
+/// the network's Jacobian - e.g. which eye cells drive a bird's turning.
+#[derive(Clone, Debug)]
+pub struct Saliency<F = f32> {
+    inputs: usize,
+
+    /// Row-major `outputs x inputs`
+    values: Vec<F>,
+}
+
+impl<F: Float> Network<F> {
+    /// Returns derivative of each output with respect to each input, at
+    /// given inputs.
+    ///
+    /// Like [`Network::propagate()`], recurrent layers behave as if it was
+    /// the very first step - see [`Network::saliency_recurrent()`].
+    pub fn saliency(&self, inputs: &[F], method: SaliencyMethod) -> Saliency<F> {
+        self.saliency_with(inputs, None, method)
+    }
+
+    /// Like [`Network::saliency()`], but for the step that comes after
+    /// given state (which remains unchanged).
+    pub fn saliency_recurrent(
+        &self,
+        inputs: &[F],
+        state: &State<F>,
+        method: SaliencyMethod,
+    ) -> Saliency<F> {
+        assert_eq!(state.layers.len(), self.layers.len());
+
+        self.saliency_with(inputs, Some(state), method)
+    }
+
+    /// Averages magnitudes of [`Network::saliency()`] over given inputs
+    /// (e.g. recorded from a bird's eye during a simulation).
+    ///
+    /// Magnitudes are used so that an input that pushes an output up
+    /// half of the time and down the other half doesn't come up as
+    /// irrelevant.
+    pub fn average_saliency<'a>(
+        &self,
+        dataset: impl IntoIterator<Item = &'a [F]>,
+        method: SaliencyMethod,
+    ) -> Saliency<F> {
+        let mut total: Option<Saliency<F>> = None;
+        let mut count = 0;
+
+        for inputs in dataset {
+            let saliency = self.saliency(inputs, method);
+
+            match &mut total {
+                Some(total) => {
+                    for (total, value) in total.values.iter_mut().zip(saliency.values) {
+                        *total += abs(value);
+                    }
+                }
+                None => {
+                    total = Some(Saliency {
+                        inputs: saliency.inputs,
+                        values: saliency.values.into_iter().map(abs).collect(),
+                    });
+                }
+            }
+
+            count += 1;
+        }
+
+        let mut total = total.expect("got an empty dataset");
+        let count = F::from_f32(count as f32);
+
+        for value in &mut total.values {
+            *value = *value / count;
+        }
+
+        total
+    }
+
+    fn saliency_with(
+        &self,
+        inputs: &[F],
+        state: Option<&State<F>>,
+        method: SaliencyMethod,
+    ) -> Saliency<F> {
+        if let Err(err) = self.check_inputs(inputs) {
+            panic!("{}", err);
+        }
+
+        match method {
+            SaliencyMethod::FiniteDifferences { step } => {
+                self.finite_differences(inputs, state, F::from_f32(step))
+            }
+            SaliencyMethod::Analytic => self.analytic(inputs, state),
+        }
+    }
+
+    fn finite_differences(&self, inputs: &[F], state: Option<&State<F>>, step: F) -> Saliency<F> {
+        let mut scratch = Scratch::default();
+
+        let mut propagate = |inputs: &[F]| -> Vec<F> {
+            match state {
+                Some(state) => self
+                    .propagate_recurrent(inputs, &mut state.clone(), &mut scratch)
+                    .to_vec(),
+                None => self.propagate_into(inputs, &mut scratch).to_vec(),
+            }
+        };
+
+        let outputs = self.layers[self.layers.len() - 1].output_size();
+        let mut values = vec![F::ZERO; outputs * inputs.len()];
+        let mut nudged = inputs.to_vec();
+
+        for i in 0..inputs.len() {
+            nudged[i] = inputs[i] + step;
+            let plus = propagate(&nudged);
+
+            nudged[i] = inputs[i] - step;
+            let minus = propagate(&nudged);
+
+            nudged[i] = inputs[i];
+
+            for (o, (plus, minus)) in plus.into_iter().zip(minus).enumerate() {
+                values[o * inputs.len() + i] = (plus - minus) / (step + step);
+            }
+        }
+
+        Saliency {
+            inputs: inputs.len(),
+            values,
+        }
+    }
+
+    fn analytic(&self, inputs: &[F], state: Option<&State<F>>) -> Saliency<F> {
+        // Recurrent connections carry what happened in the previous step,
+        // which doesn't depend on current inputs - so they only affect
+        // the point at which the activations get differentiated
+        let trace = match state {
+            Some(state) => self.trace_recurrent(inputs, &mut state.clone()),
+            None => self.trace(inputs),
+        };
+
+        // Derivatives of each output with respect to current layer's sums
+        // (row-major `outputs x neurons`), going from the last layer
+        let outputs = self.layers[self.layers.len() - 1].output_size();
+        let mut jacobian: Vec<F> = Vec::new();
+
+        for (idx, layer) in self.layers.iter().enumerate().rev() {
+            let LayerTrace {
+                sums,
+                outputs: values,
+            } = &trace.layers()[idx];
+
+            let derivatives: Vec<_> = sums
+                .iter()
+                .zip(values)
+                .map(|(&sum, &value)| layer.activation.derivative(sum, value))
+                .collect();
+
+            if idx + 1 == self.layers.len() {
+                jacobian = vec![F::ZERO; outputs * outputs];
+
+                for (o, &derivative) in derivatives.iter().enumerate() {
+                    jacobian[o * outputs + o] = derivative;
+                }
+            } else {
+                for row in jacobian.chunks_exact_mut(derivatives.len()) {
+                    for (value, &derivative) in row.iter_mut().zip(&derivatives) {
+                        *value *= derivative;
+                    }
+                }
+            }
+
+            // Through the weights (plus whatever plasticity has learned so
+            // far), onto this layer's inputs
+            let deltas = state
+                .map(|state| state.plastic[idx].as_slice())
+                .filter(|deltas| !deltas.is_empty());
+
+            let mut previous = vec![F::ZERO; outputs * layer.input_size()];
+
+            for (row, previous) in jacobian
+                .chunks_exact(layer.output_size())
+                .zip(previous.chunks_exact_mut(layer.input_size()))
+            {
+                for (n, (&value, (_, weights, _))) in row.iter().zip(layer.neurons()).enumerate() {
+                    for (i, (previous, &weight)) in previous.iter_mut().zip(weights).enumerate() {
+                        let delta = deltas.map_or(F::ZERO, |deltas| deltas[n * weights.len() + i]);
+
+                        *previous += value * (weight + delta);
+                    }
+                }
+            }
+
+            jacobian = previous;
+        }
+
+        Saliency {
+            inputs: inputs.len(),
+            values: jacobian,
+        }
+    }
+}
+
+impl<F: Float> Saliency<F> {
+    pub fn input_size(&self) -> usize {
+        self.inputs
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.values.len().checked_div(self.inputs).unwrap_or(0)
+    }
+
+    /// Returns derivative of given output with respect to given input.
+    pub fn get(&self, output: usize, input: usize) -> F {
+        assert!(input < self.inputs);
+
+        self.values[output * self.inputs + input]
+    }
+
+    /// Returns derivatives of given output with respect to each input.
+    pub fn output(&self, output: usize) -> &[F] {
+        &self.values[output * self.inputs..][..self.inputs]
+    }
+}
+
+fn abs<F: Float>(value: F) -> F {
+    if value < F::ZERO {
+        -value
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    const FINITE_DIFFERENCES: SaliencyMethod = SaliencyMethod::FiniteDifferences { step: 1e-3 };
+
+    fn network(rng: &mut ChaCha8Rng) -> Network {
+        Network::random(
+            &[
+                LayerTopology::new(5),
+                LayerTopology::with_activation(8, Activation::Tanh).recurrent(),
+                LayerTopology::with_activation(6, Activation::Sigmoid)
+                    .convolutional(Convolution::new(3).with_stride(2).with_filters(2)),
+                LayerTopology::with_activation(2, Activation::Tanh),
+            ],
+            rng,
+        )
+    }
+
+    fn inputs(rng: &mut ChaCha8Rng) -> Vec<f32> {
+        (0..5).map(|_| rng.gen_range(0.0..=1.0)).collect()
+    }
+
+    #[test]
+    fn of_linear_network_are_its_weights() {
+        let layers = &[
+            LayerTopology::new(2),
+            LayerTopology::with_activation(1, Activation::Identity),
+        ];
+
+        let network = Network::from_weights(layers, vec![0.5, -2.0, 3.0]);
+
+        for method in [SaliencyMethod::Analytic, FINITE_DIFFERENCES] {
+            let saliency = network.saliency(&[0.1, 0.2], method);
+
+            assert_eq!(saliency.input_size(), 2);
+            assert_eq!(saliency.output_size(), 1);
+            approx::assert_relative_eq!(saliency.output(0), [-2.0, 3.0].as_ref(), epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn analytic_matches_finite_differences() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for _ in 0..10 {
+            let network = network(&mut rng);
+            let inputs = inputs(&mut rng);
+
+            let analytic = network.saliency(&inputs, SaliencyMethod::Analytic);
+            let numeric = network.saliency(&inputs, FINITE_DIFFERENCES);
+
+            approx::assert_relative_eq!(
+                analytic.values.as_slice(),
+                numeric.values.as_slice(),
+                epsilon = 1e-2
+            );
+        }
+    }
+
+    #[test]
+    fn analytic_matches_finite_differences_after_given_state() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network = network(&mut rng);
+        let mut state = network.state();
+
+        network.propagate_recurrent(&inputs(&mut rng), &mut state, &mut Scratch::default());
+
+        let inputs = inputs(&mut rng);
+        let analytic = network.saliency_recurrent(&inputs, &state, SaliencyMethod::Analytic);
+        let numeric = network.saliency_recurrent(&inputs, &state, FINITE_DIFFERENCES);
+
+        approx::assert_relative_eq!(
+            analytic.values.as_slice(),
+            numeric.values.as_slice(),
+            epsilon = 1e-2
+        );
+
+        // ... and the state stays as it was
+        assert_ne!(
+            network.saliency(&inputs, SaliencyMethod::Analytic).values,
+            analytic.values
+        );
+    }
+
+    #[test]
+    fn accounts_for_what_plasticity_learned() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let layers = &[
+            LayerTopology::new(5),
+            LayerTopology::with_activation(4, Activation::Tanh).plastic(),
+            LayerTopology::with_activation(2, Activation::Tanh),
+        ];
+
+        let network: Network = Network::random(layers, &mut rng);
+        let mut state = network.state();
+
+        for _ in 0..3 {
+            network.propagate_recurrent(&inputs(&mut rng), &mut state, &mut Scratch::default());
+        }
+
+        let inputs = inputs(&mut rng);
+        let analytic = network.saliency_recurrent(&inputs, &state, SaliencyMethod::Analytic);
+        let numeric = network.saliency_recurrent(&inputs, &state, FINITE_DIFFERENCES);
+
+        approx::assert_relative_eq!(
+            analytic.values.as_slice(),
+            numeric.values.as_slice(),
+            epsilon = 1e-2
+        );
+    }
+
+    #[test]
+    fn average_uses_magnitudes() {
+        let layers = &[
+            LayerTopology::new(2),
+            LayerTopology::with_activation(1, Activation::LeakyRelu(-1.0)),
+        ];
+
+        // |x1|, ignoring x2
+        let network = Network::from_weights(layers, vec![0.0, 1.0, 0.0]);
+
+        let dataset = [[1.0, 0.5], [-1.0, 0.5], [2.0, -3.0]];
+        let saliency =
+            network.average_saliency(dataset.iter().map(|inputs| &inputs[..]), Default::default());
+
+        approx::assert_relative_eq!(saliency.output(0), [1.0, 0.0].as_ref());
+    }
+}
@@ -0,0 +1,825 @@
+use std::{error::Error, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Version of the save format produced by [`Network::to_json()`] and
+/// [`Network::to_bytes()`].
+///
+/// Bump it whenever the layout changes, so that older brains get a
+/// readable error instead of silently loading garbage.
+///
+/// - v1 = initial version,
+/// - v2 = layers can be recurrent,
+/// - v3 = layers can be convolutional,
+/// - v4 = layers can be plastic.
+pub const FORMAT_VERSION: u32 = 4;
+
+/// Oldest version we're still able to load.
+const MIN_FORMAT_VERSION: u32 = 1;
+
+/// Magic bytes every binary-encoded network starts with.
+const MAGIC: &[u8; 4] = b"SLNN";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SaveError {
+    /// [`Activation::Custom`] is a plain function pointer, so there's
+    /// no way of writing it down (or reading it back).
+    CustomActivation {
+        layer: usize,
+    },
+
+    Json(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    Json(String),
+
+    /// Binary data doesn't start with [`MAGIC`] - most likely it's not
+    /// a network at all.
+    BadMagic,
+
+    UnsupportedVersion {
+        found: u32,
+        supported: u32,
+    },
+
+    /// Binary data ended before the whole network could be read.
+    UnexpectedEof,
+
+    /// Binary data contains more bytes than the header accounts for.
+    TrailingBytes {
+        count: usize,
+    },
+
+    UnknownActivation {
+        layer: usize,
+        tag: u8,
+    },
+
+    /// See [`NetworkError::InvalidConvolution`] and
+    /// [`NetworkError::ConvolutionSizeMismatch`].
+    InvalidConvolution {
+        layer: usize,
+    },
+
+    /// A network needs at least an input and an output layer.
+    NotEnoughLayers {
+        count: usize,
+    },
+
+    WeightCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+
+    /// See [`NetworkError::TooLarge`].
+    TooLarge {
+        layer: usize,
+    },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CustomActivation { layer } => {
+                write!(
+                    f,
+                    "layer {} uses a custom activation, which can't be saved",
+                    layer
+                )
+            }
+            Self::Json(err) => write!(f, "couldn't encode network as JSON: {}", err),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "couldn't decode network from JSON: {}", err),
+            Self::BadMagic => write!(f, "data doesn't look like a saved network"),
+            Self::UnsupportedVersion { found, supported } => write!(
+                f,
+                "network was saved in format v{}, but only v{} is supported",
+                found, supported
+            ),
+            Self::UnexpectedEof => write!(f, "data ended unexpectedly"),
+            Self::TrailingBytes { count } => {
+                write!(f, "got {} unexpected bytes after the network", count)
+            }
+            Self::UnknownActivation { layer, tag } => {
+                write!(f, "layer {} uses unknown activation (tag {})", layer, tag)
+            }
+            Self::InvalidConvolution { layer } => {
+                write!(f, "layer {} has an invalid convolution", layer)
+            }
+            Self::NotEnoughLayers { count } => {
+                write!(f, "expected at least 2 layers, got {}", count)
+            }
+            Self::WeightCountMismatch { expected, actual } => write!(
+                f,
+                "topology requires {} weights, but got {}",
+                expected, actual
+            ),
+            Self::TooLarge { layer } => write!(f, "layer {} is too large", layer),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Self-describing form of a [`Network`]; that's what actually gets
+/// written into JSON.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedNetwork {
+    version: u32,
+    layers: Vec<SavedLayer>,
+    weights: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedLayer {
+    neurons: usize,
+    activation: SavedActivation,
+
+    /// Missing in v1, which didn't support recurrent layers
+    #[serde(default)]
+    recurrent: bool,
+
+    /// Missing in v1 and v2, which didn't support convolutional layers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    convolution: Option<SavedConvolution>,
+
+    /// Missing in v1 to v3, which didn't support plastic layers
+    #[serde(default)]
+    plastic: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct SavedConvolution {
+    filters: usize,
+    kernel_size: usize,
+    stride: usize,
+    circular: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum SavedActivation {
+    Relu,
+    LeakyRelu { slope: f32 },
+    Sigmoid,
+    Tanh,
+    Identity,
+}
+
+impl SavedActivation {
+    fn from_activation(activation: Activation, layer: usize) -> Result<Self, SaveError> {
+        Ok(match activation {
+            Activation::Relu => Self::Relu,
+            Activation::LeakyRelu(slope) => Self::LeakyRelu { slope },
+            Activation::Sigmoid => Self::Sigmoid,
+            Activation::Tanh => Self::Tanh,
+            Activation::Identity => Self::Identity,
+            Activation::Custom(_) => return Err(SaveError::CustomActivation { layer }),
+        })
+    }
+
+    fn into_activation(self) -> Activation {
+        match self {
+            Self::Relu => Activation::Relu,
+            Self::LeakyRelu { slope } => Activation::LeakyRelu(slope),
+            Self::Sigmoid => Activation::Sigmoid,
+            Self::Tanh => Activation::Tanh,
+            Self::Identity => Activation::Identity,
+        }
+    }
+
+    /// Returns `(tag, parameter)` used by the binary encoding.
+    fn encode(self) -> (u8, f32) {
+        match self {
+            Self::Relu => (0, 0.0),
+            Self::LeakyRelu { slope } => (1, slope),
+            Self::Sigmoid => (2, 0.0),
+            Self::Tanh => (3, 0.0),
+            Self::Identity => (4, 0.0),
+        }
+    }
+
+    fn decode(tag: u8, param: f32, layer: usize) -> Result<Self, LoadError> {
+        Ok(match tag {
+            0 => Self::Relu,
+            1 => Self::LeakyRelu { slope: param },
+            2 => Self::Sigmoid,
+            3 => Self::Tanh,
+            4 => Self::Identity,
+            tag => return Err(LoadError::UnknownActivation { layer, tag }),
+        })
+    }
+}
+
+impl SavedConvolution {
+    fn from_convolution(convolution: Convolution) -> Self {
+        Self {
+            filters: convolution.filters,
+            kernel_size: convolution.kernel_size,
+            stride: convolution.stride,
+            circular: convolution.padding == Padding::Circular,
+        }
+    }
+
+    fn into_convolution(self) -> Convolution {
+        Convolution {
+            filters: self.filters,
+            kernel_size: self.kernel_size,
+            stride: self.stride,
+            padding: if self.circular {
+                Padding::Circular
+            } else {
+                Padding::None
+            },
+        }
+    }
+}
+
+impl SavedNetwork {
+    fn from_network(network: &Network) -> Result<Self, SaveError> {
+        let layers = network
+            .topology()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, layer)| {
+                Ok(SavedLayer {
+                    neurons: layer.neurons,
+                    activation: SavedActivation::from_activation(layer.activation, idx)?,
+                    recurrent: layer.recurrent,
+                    convolution: layer.convolution.map(SavedConvolution::from_convolution),
+                    plastic: layer.plastic,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            version: FORMAT_VERSION,
+            layers,
+            weights: network.weights(),
+        })
+    }
+
+    fn into_network(self) -> Result<Network, LoadError> {
+        check_version(self.version)?;
+
+        let topology: Vec<_> = self
+            .layers
+            .iter()
+            .map(|layer| LayerTopology {
+                neurons: layer.neurons,
+                activation: layer.activation.into_activation(),
+                recurrent: layer.recurrent,
+                convolution: layer.convolution.map(SavedConvolution::into_convolution),
+                plastic: layer.plastic,
+            })
+            .collect();
+
+        Network::try_from_weights(&topology, self.weights).map_err(|err| match err {
+            NetworkError::NotEnoughLayers { count } => LoadError::NotEnoughLayers { count },
+            NetworkError::WeightCountMismatch { expected, actual } => {
+                LoadError::WeightCountMismatch { expected, actual }
+            }
+            NetworkError::InvalidConvolution { layer }
+            | NetworkError::ConvolutionSizeMismatch { layer, .. } => {
+                LoadError::InvalidConvolution { layer }
+            }
+            NetworkError::TooLarge { layer } => LoadError::TooLarge { layer },
+            err => unreachable!("{}", err),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + 12 * self.layers.len() + 4 * self.weights.len());
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&(self.layers.len() as u32).to_le_bytes());
+
+        for layer in &self.layers {
+            let (tag, param) = layer.activation.encode();
+
+            bytes.extend_from_slice(&(layer.neurons as u32).to_le_bytes());
+            bytes.push(tag);
+            bytes.extend_from_slice(&param.to_le_bytes());
+            bytes.push(layer.recurrent as u8);
+
+            match layer.convolution {
+                Some(convolution) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&(convolution.filters as u32).to_le_bytes());
+                    bytes.extend_from_slice(&(convolution.kernel_size as u32).to_le_bytes());
+                    bytes.extend_from_slice(&(convolution.stride as u32).to_le_bytes());
+                    bytes.push(convolution.circular as u8);
+                }
+                None => bytes.push(0),
+            }
+
+            bytes.push(layer.plastic as u8);
+        }
+
+        bytes.extend_from_slice(&(self.weights.len() as u32).to_le_bytes());
+
+        for weight in &self.weights {
+            bytes.extend_from_slice(&weight.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(LoadError::BadMagic);
+        }
+
+        // Checked before anything else, so that a future format can
+        // freely change what follows the header
+        let version = reader.u32()?;
+
+        check_version(version)?;
+
+        let layers = (0..reader.u32()?)
+            .map(|idx| {
+                let neurons = reader.u32()? as usize;
+                let tag = reader.u8()?;
+                let param = reader.f32()?;
+                let activation = SavedActivation::decode(tag, param, idx as usize)?;
+                let recurrent = version >= 2 && reader.u8()? != 0;
+
+                let convolution = if version >= 3 && reader.u8()? != 0 {
+                    Some(SavedConvolution {
+                        filters: reader.u32()? as usize,
+                        kernel_size: reader.u32()? as usize,
+                        stride: reader.u32()? as usize,
+                        circular: reader.u8()? != 0,
+                    })
+                } else {
+                    None
+                };
+
+                let plastic = version >= 4 && reader.u8()? != 0;
+
+                Ok(SavedLayer {
+                    neurons,
+                    activation,
+                    recurrent,
+                    convolution,
+                    plastic,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let weights = (0..reader.u32()?)
+            .map(|_| reader.f32())
+            .collect::<Result<_, _>>()?;
+
+        if !reader.bytes.is_empty() {
+            return Err(LoadError::TrailingBytes {
+                count: reader.bytes.len(),
+            });
+        }
+
+        Ok(Self {
+            version,
+            layers,
+            weights,
+        })
+    }
+}
+
+fn check_version(version: u32) -> Result<(), LoadError> {
+    if (MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
+        Ok(())
+    } else {
+        Err(LoadError::UnsupportedVersion {
+            found: version,
+            supported: FORMAT_VERSION,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        if self.bytes.len() < len {
+            return Err(LoadError::UnexpectedEof);
+        }
+
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, LoadError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, LoadError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+impl Network {
+    /// Encodes this network (topology, activations and weights) as
+    /// human-readable JSON.
+    pub fn to_json(&self) -> Result<String, SaveError> {
+        let saved = SavedNetwork::from_network(self)?;
+
+        serde_json::to_string_pretty(&saved).map_err(|err| SaveError::Json(err.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, LoadError> {
+        let saved: SavedNetwork =
+            serde_json::from_str(json).map_err(|err| LoadError::Json(err.to_string()))?;
+
+        saved.into_network()
+    }
+
+    /// Encodes this network in a compact, little-endian binary format.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SaveError> {
+        Ok(SavedNetwork::from_network(self)?.to_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        SavedNetwork::from_bytes(bytes)?.into_network()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network() -> Network {
+        let layers = &[
+            LayerTopology::new(2),
+            LayerTopology::with_activation(2, Activation::LeakyRelu(0.1)),
+            LayerTopology::with_activation(1, Activation::Tanh),
+        ];
+
+        Network::from_weights(layers, (1..=9).map(|n| n as f32 / 10.0))
+    }
+
+    fn convolutional_network() -> Network {
+        let convolution = Convolution::new(3)
+            .with_filters(2)
+            .with_stride(2)
+            .circular();
+
+        let layers = &[
+            LayerTopology::new(5),
+            LayerTopology::new(convolution.output_size(5)).convolutional(convolution),
+            LayerTopology::with_activation(1, Activation::Tanh),
+        ];
+
+        Network::from_weights(layers, (1..=15).map(|n| n as f32 / 10.0))
+    }
+
+    fn plastic_network() -> Network {
+        let layers = &[
+            LayerTopology::new(2),
+            LayerTopology::with_activation(2, Activation::Tanh).plastic(),
+            LayerTopology::new(1),
+        ];
+
+        Network::from_weights(layers, (1..=14).map(|n| n as f32 / 10.0))
+    }
+
+    fn assert_same_network(actual: &Network, expected: &Network) {
+        approx::assert_relative_eq!(actual.weights().as_slice(), expected.weights().as_slice());
+
+        let inputs: Vec<_> = [-1.0, 0.5]
+            .into_iter()
+            .cycle()
+            .take(expected.topology()[0].neurons)
+            .collect();
+
+        approx::assert_relative_eq!(
+            actual.propagate(inputs.clone()).as_slice(),
+            expected.propagate(inputs).as_slice()
+        );
+    }
+
+    mod json {
+        use super::*;
+
+        #[test]
+        fn round_trips() {
+            let network = network();
+            let json = network.to_json().unwrap();
+
+            assert_same_network(&Network::from_json(&json).unwrap(), &network);
+        }
+
+        #[test]
+        fn is_self_describing() {
+            let json: serde_json::Value =
+                serde_json::from_str(&network().to_json().unwrap()).unwrap();
+
+            assert_eq!(json["version"], FORMAT_VERSION);
+            assert_eq!(json["layers"][1]["neurons"], 2);
+            assert_eq!(json["layers"][1]["activation"]["kind"], "leaky_relu");
+            assert_eq!(json["layers"][2]["activation"]["kind"], "tanh");
+            assert_eq!(json["weights"].as_array().unwrap().len(), 9);
+        }
+
+        #[test]
+        fn loads_v1() {
+            let json = r#"{
+                "version": 1,
+                "layers": [
+                    { "neurons": 1, "activation": { "kind": "relu" } },
+                    { "neurons": 1, "activation": { "kind": "identity" } }
+                ],
+                "weights": [0.5, 2.0]
+            }"#;
+
+            let network = Network::from_json(json).unwrap();
+
+            approx::assert_relative_eq!(network.propagate(vec![-1.0]).as_slice(), [-1.5].as_ref());
+        }
+
+        #[test]
+        fn round_trips_recurrent_layers() {
+            let layers = &[
+                LayerTopology::new(1),
+                LayerTopology::with_activation(2, Activation::Tanh).recurrent(),
+                LayerTopology::new(1),
+            ];
+
+            let network = Network::from_weights(layers, (1..=11).map(|n| n as f32 / 10.0));
+            let loaded = Network::from_json(&network.to_json().unwrap()).unwrap();
+
+            assert!(loaded.topology()[1].recurrent);
+            assert_same_network(&loaded, &network);
+        }
+
+        #[test]
+        fn round_trips_convolutional_layers() {
+            let network = convolutional_network();
+            let loaded = Network::from_json(&network.to_json().unwrap()).unwrap();
+
+            assert_eq!(
+                loaded.topology()[1].convolution,
+                network.topology()[1].convolution
+            );
+            assert_same_network(&loaded, &network);
+        }
+
+        #[test]
+        fn round_trips_plastic_layers() {
+            let network = plastic_network();
+            let loaded = Network::from_json(&network.to_json().unwrap()).unwrap();
+
+            assert!(loaded.topology()[1].plastic);
+            assert_same_network(&loaded, &network);
+        }
+
+        #[test]
+        fn rejects_invalid_convolution() {
+            let json = r#"{
+                "version": 3,
+                "layers": [
+                    { "neurons": 4, "activation": { "kind": "relu" } },
+                    {
+                        "neurons": 4,
+                        "activation": { "kind": "relu" },
+                        "convolution": { "filters": 1, "kernel_size": 2, "stride": 1, "circular": false }
+                    }
+                ],
+                "weights": [0.1, 0.2, 0.3]
+            }"#;
+
+            assert_eq!(
+                Network::from_json(json).unwrap_err(),
+                LoadError::InvalidConvolution { layer: 1 }
+            );
+        }
+
+        #[test]
+        fn rejects_oversized_layers() {
+            let json = r#"{
+                "version": 1,
+                "layers": [
+                    { "neurons": 18446744073709551615, "activation": { "kind": "relu" } },
+                    { "neurons": 1, "activation": { "kind": "relu" } }
+                ],
+                "weights": []
+            }"#;
+
+            assert_eq!(
+                Network::from_json(json).unwrap_err(),
+                LoadError::TooLarge { layer: 1 }
+            );
+        }
+
+        #[test]
+        fn rejects_convolutions_too_large_to_expand() {
+            let json = r#"{
+                "version": 3,
+                "layers": [
+                    { "neurons": 4000000000, "activation": { "kind": "relu" } },
+                    {
+                        "neurons": 4000000000,
+                        "activation": { "kind": "relu" },
+                        "convolution": { "filters": 1, "kernel_size": 1, "stride": 1, "circular": false }
+                    }
+                ],
+                "weights": [0.1, 0.2]
+            }"#;
+
+            assert_eq!(
+                Network::from_json(json).unwrap_err(),
+                LoadError::TooLarge { layer: 1 }
+            );
+        }
+
+        #[test]
+        fn rejects_unsupported_version() {
+            let json = r#"{ "version": 99, "layers": [], "weights": [] }"#;
+
+            assert_eq!(
+                Network::from_json(json).unwrap_err(),
+                LoadError::UnsupportedVersion {
+                    found: 99,
+                    supported: FORMAT_VERSION,
+                }
+            );
+        }
+
+        #[test]
+        fn rejects_mismatched_weights() {
+            let json = r#"{
+                "version": 2,
+                "layers": [
+                    { "neurons": 1, "activation": { "kind": "relu" } },
+                    { "neurons": 1, "activation": { "kind": "relu" }, "recurrent": true }
+                ],
+                "weights": [0.1, 0.2]
+            }"#;
+
+            assert_eq!(
+                Network::from_json(json).unwrap_err(),
+                LoadError::WeightCountMismatch {
+                    expected: 3,
+                    actual: 2,
+                }
+            );
+        }
+
+        #[test]
+        fn rejects_custom_activation() {
+            let layers = &[
+                LayerTopology::new(1),
+                LayerTopology::with_activation(1, Activation::Custom(|x| x)),
+            ];
+
+            let network = Network::from_weights(layers, vec![0.0, 1.0]);
+
+            assert_eq!(
+                network.to_json().unwrap_err(),
+                SaveError::CustomActivation { layer: 1 }
+            );
+        }
+    }
+
+    mod bytes {
+        use super::*;
+
+        #[test]
+        fn round_trips() {
+            let network = network();
+            let bytes = network.to_bytes().unwrap();
+
+            assert_same_network(&Network::from_bytes(&bytes).unwrap(), &network);
+        }
+
+        #[test]
+        fn round_trips_convolutional_layers() {
+            let network = convolutional_network();
+            let loaded = Network::from_bytes(&network.to_bytes().unwrap()).unwrap();
+
+            assert_eq!(
+                loaded.topology()[1].convolution,
+                network.topology()[1].convolution
+            );
+            assert_same_network(&loaded, &network);
+        }
+
+        #[test]
+        fn round_trips_plastic_layers() {
+            let network = plastic_network();
+            let loaded = Network::from_bytes(&network.to_bytes().unwrap()).unwrap();
+
+            assert!(loaded.topology()[1].plastic);
+            assert_same_network(&loaded, &network);
+        }
+
+        #[test]
+        fn is_compact() {
+            // header + 3 layers + weight count + 9 weights
+            assert_eq!(network().to_bytes().unwrap().len(), 12 + 3 * 12 + 4 + 9 * 4);
+        }
+
+        #[test]
+        fn loads_v1() {
+            let mut bytes = Vec::new();
+            bytes.extend_from_slice(b"SLNN");
+            bytes.extend_from_slice(&1u32.to_le_bytes());
+            bytes.extend_from_slice(&2u32.to_le_bytes());
+
+            for _ in 0..2 {
+                // one neuron, identity, without the recurrent flag
+                bytes.extend_from_slice(&1u32.to_le_bytes());
+                bytes.push(4);
+                bytes.extend_from_slice(&0.0f32.to_le_bytes());
+            }
+
+            bytes.extend_from_slice(&2u32.to_le_bytes());
+            bytes.extend_from_slice(&0.5f32.to_le_bytes());
+            bytes.extend_from_slice(&2.0f32.to_le_bytes());
+
+            let network = Network::from_bytes(&bytes).unwrap();
+
+            approx::assert_relative_eq!(network.propagate(vec![-1.0]).as_slice(), [-1.5].as_ref());
+        }
+
+        #[test]
+        fn rejects_bad_magic() {
+            let mut bytes = network().to_bytes().unwrap();
+            bytes[0] = b'X';
+
+            assert_eq!(
+                Network::from_bytes(&bytes).unwrap_err(),
+                LoadError::BadMagic
+            );
+        }
+
+        #[test]
+        fn rejects_unsupported_version() {
+            let mut bytes = network().to_bytes().unwrap();
+            bytes[4..8].copy_from_slice(&7u32.to_le_bytes());
+
+            assert_eq!(
+                Network::from_bytes(&bytes).unwrap_err(),
+                LoadError::UnsupportedVersion {
+                    found: 7,
+                    supported: FORMAT_VERSION,
+                }
+            );
+        }
+
+        #[test]
+        fn rejects_truncated_data() {
+            let bytes = network().to_bytes().unwrap();
+
+            assert_eq!(
+                Network::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+                LoadError::UnexpectedEof
+            );
+        }
+
+        #[test]
+        fn rejects_trailing_bytes() {
+            let mut bytes = network().to_bytes().unwrap();
+            bytes.extend_from_slice(&[0, 0]);
+
+            assert_eq!(
+                Network::from_bytes(&bytes).unwrap_err(),
+                LoadError::TrailingBytes { count: 2 }
+            );
+        }
+
+        #[test]
+        fn rejects_unknown_activation() {
+            let mut bytes = network().to_bytes().unwrap();
+            // magic + version + layer count + first layer's neurons
+            bytes[16] = 42;
+
+            assert_eq!(
+                Network::from_bytes(&bytes).unwrap_err(),
+                LoadError::UnknownActivation { layer: 0, tag: 42 }
+            );
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Vectorized dot products used by [`Float::dot()`] when the `simd`
+//! feature is enabled.
+//!
+//! `wide` picks the best instructions the target has been compiled with
+//! (SSE / AVX on x86_64, simd128 on wasm32 - e.g. via
+//! `RUSTFLAGS="-C target-feature=+simd128"`) and falls back to plain
+//! scalar code everywhere else.
+
+use crate::*;
+use wide::{f32x8, f64x4};
+
+macro_rules! simd_dot {
+    ($name:ident, $ty:ty, $simd:ty, $lanes:literal) => {
+        pub(crate) fn $name(a: &[$ty], b: &[$ty]) -> $ty {
+            let len = a.len().min(b.len());
+            let (a, b) = (&a[..len], &b[..len]);
+
+            let mut sum = <$simd>::ZERO;
+            let mut a_chunks = a.chunks_exact($lanes);
+            let mut b_chunks = b.chunks_exact($lanes);
+
+            for (a, b) in (&mut a_chunks).zip(&mut b_chunks) {
+                let a = <$simd>::from(<[$ty; $lanes]>::try_from(a).unwrap());
+                let b = <$simd>::from(<[$ty; $lanes]>::try_from(b).unwrap());
+
+                sum += a * b;
+            }
+
+            sum.reduce_add() + scalar_dot(a_chunks.remainder(), b_chunks.remainder())
+        }
+    };
+}
+
+simd_dot!(dot_f32, f32, f32x8, 8);
+simd_dot!(dot_f64, f64, f64x4, 4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn vectors(rng: &mut ChaCha8Rng, len: usize) -> (Vec<f32>, Vec<f32>) {
+        let mut vector = || (0..len).map(|_| rng.gen_range(-1.0..=1.0)).collect();
+
+        (vector(), vector())
+    }
+
+    #[test]
+    fn matches_scalar_dot() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        // Covers empty vectors, vectors shorter than a single lane and
+        // vectors with remainders
+        for len in 0..=40 {
+            let (a, b) = vectors(&mut rng, len);
+
+            approx::assert_relative_eq!(dot_f32(&a, &b), scalar_dot(&a, &b), epsilon = 1e-5);
+
+            let a: Vec<_> = a.into_iter().map(f64::from).collect();
+            let b: Vec<_> = b.into_iter().map(f64::from).collect();
+
+            approx::assert_relative_eq!(dot_f64(&a, &b), scalar_dot(&a, &b), epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn ignores_excess_of_longer_slice() {
+        let a: Vec<_> = (1..=20).map(|n| n as f32).collect();
+
+        approx::assert_relative_eq!(dot_f32(&a, &a[..11]), scalar_dot(&a[..11], &a[..11]));
+        approx::assert_relative_eq!(dot_f32(&a[..3], &a), 1.0 + 4.0 + 9.0);
+    }
+
+    #[test]
+    fn network_matches_scalar_path() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        let layers = &[
+            LayerTopology::new(37),
+            LayerTopology::with_activation(19, Activation::Sigmoid),
+            LayerTopology::with_activation(3, Activation::Tanh),
+        ];
+
+        let network = Network::random(layers, &mut rng);
+        let weights = network.weights();
+
+        for _ in 0..10 {
+            let (inputs, _) = vectors(&mut rng, 37);
+
+            // Scalar re-implementation of `Network::propagate()`
+            let mut expected = inputs.clone();
+            let mut weights = weights.iter().copied();
+
+            for layer in &layers[1..] {
+                expected = (0..layer.neurons)
+                    .map(|_| {
+                        let bias = weights.next().unwrap();
+                        let row: Vec<_> = weights.by_ref().take(expected.len()).collect();
+
+                        layer.activation.apply(bias + scalar_dot(&expected, &row))
+                    })
+                    .collect();
+            }
+
+            approx::assert_relative_eq!(
+                network.propagate(inputs).as_slice(),
+                expected.as_slice(),
+                epsilon = 1e-5
+            );
+        }
+    }
+}
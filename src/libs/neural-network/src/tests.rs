@@ -9,12 +9,14 @@ mod created_randomly {
     #[test]
     fn neuron_is_created_correctly() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
-        let neuron = Neuron::random(4, &mut rng);
+        let layer =
+            Layer::<f32>::random(4, LayerTopology::new(1), Initializer::default(), &mut rng);
+        let (bias, weights, _) = layer.neurons().next().unwrap();
 
-        approx::assert_relative_eq!(neuron.bias, -0.6255188);
+        approx::assert_relative_eq!(bias, -0.6255188);
 
         approx::assert_relative_eq!(
-            neuron.weights.as_slice(),
+            weights,
             [0.67383957, 0.8181262, 0.26284897, 0.5238807,].as_ref()
         );
     }
@@ -28,20 +30,26 @@ mod created_randomly {
     mod propagate {
         use super::*;
 
+        fn propagate(layer: &Layer, inputs: &[f32]) -> Vec<f32> {
+            let mut outputs = Vec::new();
+            layer.propagate_into(inputs, None, None, &mut outputs);
+            outputs
+        }
+
         #[test]
         fn neuron_propagates_correctly() {
-            let neuron = Neuron {
-                bias: 0.5,
-                weights: vec![-0.3, 0.8],
-            };
+            let neuron = Layer::new(vec![0.5], vec![vec![-0.3, 0.8]]);
 
             // Ensures `.max()` (our ReLU) works:
-            approx::assert_relative_eq!(neuron.propagate(&[-10.0, -10.0]), 0.0,);
+            approx::assert_relative_eq!(
+                propagate(&neuron, &[-10.0, -10.0]).as_slice(),
+                [0.0].as_ref(),
+            );
 
             // `0.5` and `1.0` chosen by a fair dice roll:
             approx::assert_relative_eq!(
-                neuron.propagate(&[0.5, 1.0]),
-                (-0.3 * 0.5) + (0.8 * 1.0) + 0.5,
+                propagate(&neuron, &[0.5, 1.0]).as_slice(),
+                [(-0.3 * 0.5) + (0.8 * 1.0) + 0.5].as_ref(),
             );
 
             // We could've written `1.15` right away, but showing the entire
@@ -49,10 +57,106 @@ mod created_randomly {
         }
 
         #[test]
-        fn layer_propagates_correctly() {}
+        fn neuron_propagates_through_given_activation() {
+            let neuron = Layer::new(vec![0.5], vec![vec![-0.3, 0.8]]);
+
+            // Tanh lets negative values through, unlike ReLU:
+            approx::assert_relative_eq!(
+                propagate(&neuron.with_activation(Activation::Tanh), &[-10.0, -10.0]).as_slice(),
+                [((-0.3 * -10.0) + (0.8 * -10.0) + 0.5_f32).tanh()].as_ref(),
+            );
+
+            let neuron = Layer::new(vec![0.5], vec![vec![-0.3, 0.8]]);
+
+            approx::assert_relative_eq!(
+                propagate(
+                    &neuron.with_activation(Activation::Identity),
+                    &[-10.0, -10.0]
+                )
+                .as_slice(),
+                [(-0.3 * -10.0) + (0.8 * -10.0) + 0.5].as_ref(),
+            );
+        }
+
+        #[test]
+        fn layer_propagates_correctly() {
+            let layer = Layer::new(vec![0.5, -0.5], vec![vec![-0.3, 0.8], vec![0.2, 0.4]]);
+
+            approx::assert_relative_eq!(
+                propagate(&layer, &[0.5, 1.0]).as_slice(),
+                [
+                    (-0.3 * 0.5) + (0.8 * 1.0) + 0.5,
+                    (0.2 * 0.5) + (0.4 * 1.0) - 0.5,
+                ]
+                .as_ref(),
+            );
+        }
+
+        #[test]
+        fn layer_reuses_output_buffer() {
+            let layer = Layer::new(vec![0.5, -0.5], vec![vec![-0.3, 0.8], vec![0.2, 0.4]]);
+            let mut outputs = vec![9.0; 16];
+            let capacity = outputs.capacity();
+
+            layer.propagate_into(&[0.5, 1.0], None, None, &mut outputs);
+
+            assert_eq!(outputs.len(), 2);
+            assert_eq!(outputs.capacity(), capacity);
+        }
+
+        #[test]
+        fn network_propagates_correctly() {
+            let network = Network::new(vec![
+                Layer::new(vec![0.0, 1.0], vec![vec![1.0, 2.0], vec![-1.0, 0.5]]),
+                Layer::new(vec![0.1], vec![vec![0.5, 0.25]]),
+            ]);
+
+            // hidden = [relu(1.0 + 4.0), relu(1.0 - 1.0 + 1.0)] = [5.0, 1.0]
+            // output = relu(0.1 + 2.5 + 0.25)
+            approx::assert_relative_eq!(
+                network.propagate(vec![1.0, 2.0]).as_slice(),
+                [2.85].as_ref(),
+            );
+        }
+
+        #[test]
+        fn network_propagates_into_scratch() {
+            let network = Network::new(vec![
+                Layer::new(vec![0.0, 1.0], vec![vec![1.0, 2.0], vec![-1.0, 0.5]]),
+                Layer::new(
+                    vec![0.1, 0.0, 0.0],
+                    vec![vec![0.5, 0.25], vec![1.0, 0.0], vec![0.0, 1.0]],
+                ),
+                Layer::new(vec![0.0], vec![vec![1.0, 1.0, 1.0]]),
+            ]);
+
+            let mut scratch = Scratch::default();
+
+            for inputs in [[1.0, 2.0], [0.0, 0.0], [-1.0, 3.0]] {
+                let expected = network.propagate(inputs.to_vec());
+                let actual = network.propagate_into(&inputs, &mut scratch);
+
+                approx::assert_relative_eq!(actual, expected.as_slice());
+            }
+        }
 
         #[test]
-        fn network_propagates_correctly() {}
+        fn network_honors_per_layer_activations() {
+            let layers = &[
+                LayerTopology::new(2),
+                LayerTopology::with_activation(1, Activation::Identity),
+                LayerTopology::with_activation(1, Activation::Tanh),
+            ];
+
+            let network = Network::from_weights(layers, vec![-1.0, 1.0, 1.0, 0.0, 2.0]);
+
+            // hidden = identity(-1.0 + 1.0 * 0.5 + 1.0 * -3.0) = -3.5
+            // output = tanh(0.0 + 2.0 * -3.5) = tanh(-7.0)
+            approx::assert_relative_eq!(
+                network.propagate(vec![0.5, -3.0]).as_slice(),
+                [(-7.0_f32).tanh()].as_ref(),
+            );
+        }
     }
     mod weights {
         use super::*;
@@ -60,8 +164,8 @@ mod created_randomly {
         #[test]
         fn test() {
             let network = Network::new(vec![
-                Layer::new(vec![Neuron::new(0.1, vec![0.2, 0.3, 0.4])]),
-                Layer::new(vec![Neuron::new(0.5, vec![0.6, 0.7, 0.8])]),
+                Layer::new(vec![0.1], vec![vec![0.2, 0.3, 0.4]]),
+                Layer::new(vec![0.5], vec![vec![0.6, 0.7, 0.8]]),
             ]);
 
             let actual = network.weights();
@@ -71,12 +175,29 @@ mod created_randomly {
         }
     }
 
+    mod weights_of_wider_layers {
+        use super::*;
+
+        #[test]
+        fn are_ordered_neuron_by_neuron() {
+            let network = Network::new(vec![
+                Layer::new(vec![0.1, 0.4], vec![vec![0.2, 0.3], vec![0.5, 0.6]]),
+                Layer::new(vec![0.7], vec![vec![0.8, 0.9]]),
+            ]);
+
+            let actual = network.weights();
+            let expected = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+
+            approx::assert_relative_eq!(actual.as_slice(), expected.as_slice(),);
+        }
+    }
+
     mod from_weights {
         use super::*;
 
         #[test]
         fn test() {
-            let layers = &[LayerTopology { neurons: 3 }, LayerTopology { neurons: 2 }];
+            let layers = &[LayerTopology::new(3), LayerTopology::new(2)];
 
             let weights = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
 
@@ -86,4 +207,237 @@ mod created_randomly {
             approx::assert_relative_eq!(actual.as_slice(), weights.as_slice(),);
         }
     }
+
+    mod errors {
+        use super::*;
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        #[test]
+        fn not_enough_layers() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let layers = &[LayerTopology::new(3)];
+
+            assert_eq!(
+                Network::<f32>::try_random(layers, &mut rng).unwrap_err(),
+                NetworkError::NotEnoughLayers { count: 1 }
+            );
+
+            assert_eq!(
+                Network::<f32>::try_from_weights(layers, vec![]).unwrap_err(),
+                NetworkError::NotEnoughLayers { count: 1 }
+            );
+        }
+
+        #[test]
+        fn not_enough_weights() {
+            let layers = &[LayerTopology::new(3), LayerTopology::new(2)];
+
+            assert_eq!(
+                Network::try_from_weights(layers, vec![0.0; 7]).unwrap_err(),
+                NetworkError::WeightCountMismatch {
+                    expected: 8,
+                    actual: 7,
+                }
+            );
+        }
+
+        #[test]
+        fn too_many_weights() {
+            let layers = &[LayerTopology::new(3), LayerTopology::new(2).recurrent()];
+
+            assert_eq!(
+                Network::try_from_weights(layers, vec![0.0; 13]).unwrap_err(),
+                NetworkError::WeightCountMismatch {
+                    expected: 12,
+                    actual: 13,
+                }
+            );
+        }
+
+        #[test]
+        fn input_size_mismatch() {
+            let layers = &[LayerTopology::new(3), LayerTopology::new(2)];
+            let network = Network::from_weights(layers, vec![0.5; 8]);
+
+            assert_eq!(
+                network.try_propagate(vec![1.0, 2.0]).unwrap_err(),
+                NetworkError::InputSizeMismatch {
+                    expected: 3,
+                    actual: 2,
+                }
+            );
+
+            approx::assert_relative_eq!(
+                network
+                    .try_propagate(vec![1.0, 2.0, 3.0])
+                    .unwrap()
+                    .as_slice(),
+                [3.5, 3.5].as_ref()
+            );
+        }
+    }
+}
+
+mod recurrent {
+    use super::*;
+
+    /// One input, one recurrent identity neuron that adds up everything
+    /// it's ever seen, followed by a plain identity output.
+    fn accumulator() -> Network {
+        let layers = &[
+            LayerTopology::new(1),
+            LayerTopology::with_activation(1, Activation::Identity).recurrent(),
+            LayerTopology::with_activation(1, Activation::Identity),
+        ];
+
+        // hidden: bias, input weight, recurrent weight
+        // output: bias, weight
+        Network::from_weights(layers, vec![0.0, 1.0, 1.0, 0.0, 2.0])
+    }
+
+    #[test]
+    fn weights_include_recurrent_weights() {
+        let layers = &[
+            LayerTopology::new(2),
+            LayerTopology::new(2).recurrent(),
+            LayerTopology::new(1),
+        ];
+
+        let weights: Vec<_> = (1..=13).map(|n| n as f32).collect();
+        let network = Network::from_weights(layers, weights.clone());
+
+        approx::assert_relative_eq!(network.weights().as_slice(), weights.as_slice());
+    }
+
+    #[test]
+    fn remembers_previous_steps() {
+        let network = accumulator();
+        let mut state = network.state();
+        let mut scratch = Scratch::default();
+
+        let actual: Vec<_> = [1.0, 2.0, 3.0]
+            .iter()
+            .map(|&input| network.propagate_recurrent(&[input], &mut state, &mut scratch)[0])
+            .collect();
+
+        approx::assert_relative_eq!(actual.as_slice(), [2.0, 6.0, 12.0].as_ref());
+    }
+
+    #[test]
+    fn forgets_everything_after_reset() {
+        let network = accumulator();
+        let mut state = network.state();
+        let mut scratch = Scratch::default();
+
+        network.propagate_recurrent(&[5.0], &mut state, &mut scratch);
+        state.reset();
+
+        approx::assert_relative_eq!(
+            network.propagate_recurrent(&[1.0], &mut state, &mut scratch),
+            [2.0].as_ref()
+        );
+    }
+
+    #[test]
+    fn stateless_propagate_starts_from_scratch() {
+        let network = accumulator();
+
+        approx::assert_relative_eq!(network.propagate(vec![1.0]).as_slice(), [2.0].as_ref());
+        approx::assert_relative_eq!(network.propagate(vec![1.0]).as_slice(), [2.0].as_ref());
+    }
+}
+
+mod f64 {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn layers() -> [LayerTopology; 3] {
+        [
+            LayerTopology::new(3),
+            LayerTopology::with_activation(4, Activation::Sigmoid).recurrent(),
+            LayerTopology::with_activation(2, Activation::Tanh),
+        ]
+    }
+
+    #[test]
+    fn random_networks_match_f32_ones() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network32: Network<f32> = Network::random(&layers(), &mut rng);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network64: Network<f64> = Network::random(&layers(), &mut rng);
+
+        let weights32: Vec<_> = network32.weights().into_iter().map(f64::from).collect();
+
+        assert_eq!(network64.weights(), weights32);
+    }
+
+    #[test]
+    fn propagates_like_f32() {
+        let weights: Vec<_> = (0..weight_count(&layers()))
+            .map(|n| (n as f32 * 0.37).sin())
+            .collect();
+
+        let network32 = Network::from_weights(&layers(), weights.clone());
+        let network64 = Network::from_weights(&layers(), weights.into_iter().map(f64::from));
+
+        let outputs32 = network32.propagate(vec![0.5, -1.0, 2.0]);
+        let outputs64 = network64.propagate(vec![0.5, -1.0, 2.0]);
+
+        for (output32, output64) in outputs32.into_iter().zip(outputs64) {
+            approx::assert_relative_eq!(f64::from(output32), output64, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn keeps_precision() {
+        let layers = &[
+            LayerTopology::new(1),
+            LayerTopology::with_activation(1, Activation::Identity),
+        ];
+
+        // 1e-10 gets lost when added to 1.0 in `f32`, but not in `f64`
+        let network = Network::from_weights(layers, vec![1e-10_f64, 1.0]);
+
+        assert_eq!(network.propagate(vec![1.0]), vec![1.0 + 1e-10]);
+    }
+}
+
+/// Golden values recorded with the `deterministic` feature enabled; they
+/// must come out exactly the same on every platform.
+#[cfg(feature = "deterministic")]
+mod deterministic {
+    use super::*;
+
+    #[test]
+    fn outputs_are_bit_exact() {
+        let layers = &[
+            LayerTopology::new(3),
+            LayerTopology::with_activation(4, Activation::Sigmoid).recurrent(),
+            LayerTopology::with_activation(2, Activation::Tanh),
+        ];
+
+        let weights: Vec<_> = (0..weight_count(layers))
+            .map(|n| (n as f32 * 0.37) % 2.0 - 1.0)
+            .collect();
+
+        let network = Network::from_weights(layers, weights);
+        let mut state = network.state();
+        let mut scratch = Scratch::default();
+
+        let outputs: Vec<_> = [[0.5, -1.0, 2.0], [0.25, 0.0, -0.75]]
+            .iter()
+            .flat_map(|inputs| {
+                network
+                    .propagate_recurrent(inputs, &mut state, &mut scratch)
+                    .iter()
+                    .map(|output| output.to_bits())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        assert_eq!(outputs, [0x3f27f201, 0x3eac5692, 0x3ef229d5, 0x3dd904ec]);
+    }
 }
@@ -0,0 +1,198 @@
+use crate::*;
+
+/// Everything a network computed for a single input - returned by
+/// [`Network::trace()`], meant for looking inside a brain.
+///
+/// Layers are numbered like in [`State`], i.e. starting from the first
+/// layer that actually computes something (the input layer isn't here,
+/// since [`Trace::inputs()`] already describes it).
+#[derive(Clone, Debug)]
+pub struct Trace<F = f32> {
+    inputs: Vec<F>,
+    layers: Vec<LayerTrace<F>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LayerTrace<F = f32> {
+    /// Values of each neuron before the activation
+    pub sums: Vec<F>,
+
+    /// Values of each neuron after the activation, i.e. what the next
+    /// layer gets to see
+    pub outputs: Vec<F>,
+}
+
+/// Read-only view of a single neuron, see [`Network::neurons()`].
+#[derive(Clone, Copy, Debug)]
+pub struct Neuron<'a, F = f32> {
+    pub bias: F,
+    pub weights: &'a [F],
+
+    /// Empty for neurons of non-recurrent layers.
+    pub recurrent_weights: &'a [F],
+}
+
+impl<F: Float> Network<F> {
+    /// Propagates inputs, remembering what each layer computed along
+    /// the way.
+    ///
+    /// Like [`Network::propagate()`], recurrent layers behave as if it
+    /// was the very first step.
+    pub fn trace(&self, inputs: &[F]) -> Trace<F> {
+        self.trace_with(inputs, None)
+    }
+
+    /// Like [`Network::propagate_recurrent()`], but remembers what each
+    /// layer computed along the way.
+    ///
+    /// `state` gets updated, as usual - trace a clone of it to peek into
+    /// the network without affecting its next step.
+    pub fn trace_recurrent(&self, inputs: &[F], state: &mut State<F>) -> Trace<F> {
+        assert_eq!(state.layers.len(), self.layers.len());
+
+        self.trace_with(inputs, Some(state))
+    }
+
+    fn trace_with(&self, inputs: &[F], mut state: Option<&mut State<F>>) -> Trace<F> {
+        if let Err(err) = self.check_inputs(inputs) {
+            panic!("{}", err);
+        }
+
+        let mut layers: Vec<LayerTrace<F>> = Vec::with_capacity(self.layers.len());
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let inputs = layers.last().map_or(inputs, |layer| &layer.outputs);
+            let previous = state.as_deref().map(|state| state.layers[idx].as_slice());
+            let deltas = state.as_deref().map(|state| state.plastic[idx].as_slice());
+            let trace = layer.trace(inputs, previous, deltas);
+
+            if let Some(state) = &mut state {
+                if layer.is_recurrent() {
+                    state.layers[idx].copy_from_slice(&trace.outputs);
+                }
+
+                layer.learn(inputs, &trace.outputs, &mut state.plastic[idx]);
+            }
+
+            layers.push(trace);
+        }
+
+        Trace {
+            inputs: inputs.to_vec(),
+            layers,
+        }
+    }
+
+    /// Iterates over neurons of given layer, numbered like in [`Trace`]
+    /// (i.e. `0` is the first layer after the input one).
+    ///
+    /// Neurons of convolutional layers report weights for all of the
+    /// inputs, with zeros outside of their window; neurons of plastic
+    /// layers report weights they started with (i.e. without whatever a
+    /// [`State`] has learned).
+    pub fn neurons(&self, layer: usize) -> impl Iterator<Item = Neuron<'_, F>> {
+        self.layers[layer]
+            .neurons()
+            .map(|(bias, weights, recurrent_weights)| Neuron {
+                bias,
+                weights,
+                recurrent_weights,
+            })
+    }
+}
+
+impl<F> Trace<F> {
+    pub fn inputs(&self) -> &[F] {
+        &self.inputs
+    }
+
+    pub fn layers(&self) -> &[LayerTrace<F>] {
+        &self.layers
+    }
+
+    /// Returns what the network as a whole responded with - the same
+    /// thing [`Network::propagate()`] would.
+    pub fn outputs(&self) -> &[F] {
+        &self.layers.last().unwrap().outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network() -> Network {
+        let layers = &[
+            LayerTopology::new(2),
+            LayerTopology::new(2),
+            LayerTopology::with_activation(1, Activation::Tanh),
+        ];
+
+        // hidden: [bias, w, w] x 2
+        // output: [bias, w, w]
+        Network::from_weights(layers, vec![0.0, 1.0, 2.0, 1.0, -1.0, 0.5, 0.1, 0.5, 0.25])
+    }
+
+    #[test]
+    fn records_sums_and_outputs() {
+        let trace = network().trace(&[1.0, -2.0]);
+
+        assert_eq!(trace.inputs(), [1.0, -2.0]);
+        assert_eq!(trace.layers().len(), 2);
+
+        // hidden = relu([1.0 - 4.0, 1.0 - 1.0 - 1.0]) = relu([-3.0, -1.0])
+        approx::assert_relative_eq!(trace.layers()[0].sums.as_slice(), [-3.0, -1.0].as_ref());
+        approx::assert_relative_eq!(trace.layers()[0].outputs.as_slice(), [0.0, 0.0].as_ref());
+
+        approx::assert_relative_eq!(trace.layers()[1].sums.as_slice(), [0.1].as_ref());
+        approx::assert_relative_eq!(trace.outputs(), [0.1f32.tanh()].as_ref());
+    }
+
+    #[test]
+    fn matches_propagate() {
+        let network = network();
+
+        for inputs in [[1.0, 2.0], [0.0, 0.0], [-1.0, 3.0]] {
+            approx::assert_relative_eq!(
+                network.trace(&inputs).outputs(),
+                network.propagate(inputs.to_vec()).as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn updates_recurrent_state() {
+        let layers = &[
+            LayerTopology::new(1),
+            LayerTopology::with_activation(1, Activation::Identity).recurrent(),
+        ];
+
+        let network = Network::from_weights(layers, vec![0.0, 1.0, 1.0]);
+        let mut state = network.state();
+
+        network.trace_recurrent(&[1.0], &mut state);
+        let trace = network.trace_recurrent(&[2.0], &mut state);
+
+        approx::assert_relative_eq!(trace.layers()[0].sums.as_slice(), [3.0].as_ref());
+
+        let mut scratch = Scratch::default();
+
+        approx::assert_relative_eq!(
+            network.propagate_recurrent(&[0.0], &mut state, &mut scratch),
+            [3.0].as_ref()
+        );
+    }
+
+    #[test]
+    fn exposes_neurons() {
+        let network = network();
+        let neurons: Vec<_> = network.neurons(0).collect();
+
+        assert_eq!(neurons.len(), 2);
+        assert_eq!(neurons[1].bias, 1.0);
+        assert_eq!(neurons[1].weights, [-1.0, 0.5]);
+        assert!(neurons[1].recurrent_weights.is_empty());
+
+        assert_eq!(network.neurons(1).next().unwrap().weights, [0.5, 0.25]);
+    }
+}
@@ -0,0 +1,449 @@
+use rand::seq::SliceRandom;
+
+use crate::*;
+
+/// Pair of `(inputs, expected outputs)`, e.g. what a "good bird" saw
+/// and how it reacted.
+pub type Sample = (Vec<f32>, Vec<f32>);
+
+#[derive(Clone, Copy, Debug)]
+pub enum Loss {
+    /// Mean of squared differences between outputs and targets.
+    MeanSquaredError,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Optimizer {
+    /// Plain stochastic gradient descent.
+    Sgd { learning_rate: f32 },
+
+    /// Adam, as described in https://arxiv.org/abs/1412.6980.
+    Adam {
+        learning_rate: f32,
+        beta1: f32,
+        beta2: f32,
+        epsilon: f32,
+    },
+}
+
+/// Trains a [`Network`] with backpropagation on recorded samples.
+///
+/// Recurrent layers are trained as if they were feed-forward (i.e. as if
+/// each sample was the very first step); their recurrent weights are
+/// left as they are.
+#[derive(Clone, Debug)]
+pub struct Trainer {
+    loss: Loss,
+    optimizer: Optimizer,
+    batch_size: usize,
+
+    /// Adam's moment estimates, in the order of `Network::weights()`
+    moments: Vec<(f32, f32)>,
+
+    /// Number of optimizer steps performed so far
+    steps: i32,
+}
+
+impl Loss {
+    pub fn loss(&self, outputs: &[f32], targets: &[f32]) -> f32 {
+        assert_eq!(outputs.len(), targets.len());
+
+        match self {
+            Self::MeanSquaredError => {
+                let sum = outputs
+                    .iter()
+                    .zip(targets)
+                    .map(|(output, target)| (output - target).powi(2))
+                    .sum::<f32>();
+
+                sum / (outputs.len() as f32)
+            }
+        }
+    }
+
+    /// Returns derivative of the loss with respect to given output.
+    fn gradient(&self, output: f32, target: f32, outputs: usize) -> f32 {
+        match self {
+            Self::MeanSquaredError => 2.0 * (output - target) / (outputs as f32),
+        }
+    }
+}
+
+impl Optimizer {
+    pub fn sgd(learning_rate: f32) -> Self {
+        Self::Sgd { learning_rate }
+    }
+
+    /// Adam with the usual defaults for betas and epsilon.
+    pub fn adam(learning_rate: f32) -> Self {
+        Self::Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+        }
+    }
+}
+
+impl Trainer {
+    pub fn new(loss: Loss, optimizer: Optimizer, batch_size: usize) -> Self {
+        assert!(batch_size > 0);
+
+        Self {
+            loss,
+            optimizer,
+            batch_size,
+            moments: Vec::new(),
+            steps: 0,
+        }
+    }
+
+    /// Trains the network for given number of epochs, shuffling samples
+    /// before each one; returns average loss of each epoch.
+    pub fn fit(
+        &mut self,
+        network: &mut Network,
+        samples: &[Sample],
+        epochs: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Vec<f32> {
+        assert!(!samples.is_empty());
+
+        let mut order: Vec<_> = (0..samples.len()).collect();
+        let mut batch = Vec::with_capacity(self.batch_size);
+
+        (0..epochs)
+            .map(|_| {
+                order.shuffle(rng);
+
+                let mut loss = 0.0;
+
+                for chunk in order.chunks(self.batch_size) {
+                    batch.clear();
+                    batch.extend(chunk.iter().map(|&idx| samples[idx].clone()));
+
+                    loss += self.train_batch(network, &batch) * (chunk.len() as f32);
+                }
+
+                loss / (samples.len() as f32)
+            })
+            .collect()
+    }
+
+    /// Performs a single optimizer step over given batch; returns the
+    /// batch's average loss (from before the step).
+    pub fn train_batch(&mut self, network: &mut Network, batch: &[Sample]) -> f32 {
+        assert!(!batch.is_empty());
+
+        let mut gradients = vec![0.0; parameter_count(network)];
+        let mut loss = 0.0;
+
+        for (inputs, targets) in batch {
+            loss += backpropagate(network, self.loss, inputs, targets, &mut gradients);
+        }
+
+        for gradient in &mut gradients {
+            *gradient /= batch.len() as f32;
+        }
+
+        self.step(network, &gradients);
+
+        loss / (batch.len() as f32)
+    }
+
+    fn step(&mut self, network: &mut Network, gradients: &[f32]) {
+        self.steps += 1;
+
+        if self.moments.len() != gradients.len() {
+            self.moments = vec![(0.0, 0.0); gradients.len()];
+        }
+
+        let mut gradients = gradients.iter();
+        let mut moments = self.moments.iter_mut();
+
+        match self.optimizer {
+            Optimizer::Sgd { learning_rate } => {
+                for layer in &mut network.layers {
+                    layer.update_parameters(|param| {
+                        *param -= learning_rate * gradients.next().unwrap();
+                    });
+                }
+            }
+
+            Optimizer::Adam {
+                learning_rate,
+                beta1,
+                beta2,
+                epsilon,
+            } => {
+                let bias_correction1 = 1.0 - beta1.powi(self.steps);
+                let bias_correction2 = 1.0 - beta2.powi(self.steps);
+
+                for layer in &mut network.layers {
+                    layer.update_parameters(|param| {
+                        let gradient = gradients.next().unwrap();
+                        let (m, v) = moments.next().unwrap();
+
+                        *m = beta1 * *m + (1.0 - beta1) * gradient;
+                        *v = beta2 * *v + (1.0 - beta2) * gradient * gradient;
+
+                        let m_hat = *m / bias_correction1;
+                        let v_hat = *v / bias_correction2;
+
+                        *param -= learning_rate * m_hat / (v_hat.sqrt() + epsilon);
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn parameter_count(network: &Network) -> usize {
+    network.layers.iter().map(Layer::parameter_count).sum()
+}
+
+/// Accumulates gradients of the loss for a single sample into
+/// `gradients` (laid out like `Network::weights()`); returns the loss.
+fn backpropagate(
+    network: &Network,
+    loss: Loss,
+    inputs: &[f32],
+    targets: &[f32],
+    gradients: &mut [f32],
+) -> f32 {
+    // Forward pass, remembering what each layer computed
+    let trace = network.trace(inputs);
+    let predicted = trace.outputs();
+    assert_eq!(predicted.len(), targets.len());
+
+    // Backward pass; `deltas` are derivatives of the loss with respect to
+    // current layer's sums
+    let mut deltas: Vec<_> = predicted
+        .iter()
+        .zip(targets)
+        .map(|(&output, &target)| loss.gradient(output, target, targets.len()))
+        .collect();
+
+    let mut offset = gradients.len();
+
+    for (idx, layer) in network.layers.iter().enumerate().rev() {
+        let LayerTrace { sums, outputs } = &trace.layers()[idx];
+
+        for ((delta, &sum), &output) in deltas.iter_mut().zip(sums).zip(outputs) {
+            *delta *= layer.activation.derivative(sum, output);
+        }
+
+        offset -= layer.parameter_count();
+
+        let inputs = match idx {
+            0 => trace.inputs(),
+            idx => &trace.layers()[idx - 1].outputs,
+        };
+        let parameters = &mut gradients[offset..][..layer.parameter_count()];
+
+        if let Some(convolution) = layer.convolution() {
+            // Filters are shared, so they collect gradients from all of
+            // the positions they've been applied at
+            let positions = convolution.positions(layer.input_size());
+
+            for (n, delta) in deltas.iter().enumerate() {
+                parameters[convolution.bias_of(n / positions)] += delta;
+            }
+
+            for (n, input, parameter) in convolution.taps(layer.input_size()) {
+                parameters[parameter] += deltas[n] * inputs[input];
+            }
+        } else {
+            let recurrent = if layer.is_recurrent() {
+                layer.output_size()
+            } else {
+                0
+            };
+
+            let stride = 1 + layer.input_size() + recurrent;
+
+            for (n, delta) in deltas.iter().enumerate() {
+                let neuron = &mut parameters[n * stride..][..stride];
+
+                neuron[0] += delta;
+
+                for (gradient, input) in neuron[1..].iter_mut().zip(inputs) {
+                    *gradient += delta * input;
+                }
+            }
+        }
+
+        if idx > 0 {
+            let mut previous = vec![0.0; layer.input_size()];
+
+            for ((_, weights, _), delta) in layer.neurons().zip(&deltas) {
+                for (previous, weight) in previous.iter_mut().zip(weights) {
+                    *previous += weight * delta;
+                }
+            }
+
+            deltas = previous;
+        }
+    }
+
+    loss.loss(predicted, targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn network(rng: &mut ChaCha8Rng) -> Network {
+        Network::random(
+            &[
+                LayerTopology::new(2),
+                LayerTopology::with_activation(4, Activation::Tanh),
+                LayerTopology::with_activation(1, Activation::Identity),
+            ],
+            rng,
+        )
+    }
+
+    fn samples() -> Vec<Sample> {
+        // y = x1 - 2 * x2
+        (0..20)
+            .map(|n| {
+                let x1 = (n % 5) as f32 / 5.0 - 0.4;
+                let x2 = (n / 5) as f32 / 4.0 - 0.4;
+
+                (vec![x1, x2], vec![x1 - 2.0 * x2])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mean_squared_error() {
+        approx::assert_relative_eq!(
+            Loss::MeanSquaredError.loss(&[1.0, 2.0], &[0.0, 4.0]),
+            (1.0 + 4.0) / 2.0
+        );
+    }
+
+    fn assert_gradients_match_finite_differences(
+        network: &Network,
+        inputs: &[f32],
+        targets: &[f32],
+    ) {
+        let mut gradients = vec![0.0; parameter_count(network)];
+        backpropagate(
+            network,
+            Loss::MeanSquaredError,
+            inputs,
+            targets,
+            &mut gradients,
+        );
+
+        let topology = network.topology();
+        let weights = network.weights();
+
+        let loss_with = |weights: Vec<f32>| {
+            let network = Network::from_weights(&topology, weights);
+            Loss::MeanSquaredError.loss(&network.propagate(inputs.to_vec()), targets)
+        };
+
+        for (idx, &gradient) in gradients.iter().enumerate() {
+            let h = 1e-2;
+
+            let mut plus = weights.clone();
+            plus[idx] += h;
+
+            let mut minus = weights.clone();
+            minus[idx] -= h;
+
+            let expected = (loss_with(plus) - loss_with(minus)) / (2.0 * h);
+
+            approx::assert_relative_eq!(gradient, expected, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn gradients_match_finite_differences() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network = network(&mut rng);
+        let (inputs, targets) = &samples()[7];
+
+        assert_gradients_match_finite_differences(&network, inputs, targets);
+    }
+
+    #[test]
+    fn gradients_of_convolutions_match_finite_differences() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for convolution in [
+            Convolution::new(3).with_filters(2),
+            Convolution::new(3).with_stride(2).circular(),
+        ] {
+            let network = Network::random(
+                &[
+                    LayerTopology::new(6),
+                    LayerTopology::with_activation(convolution.output_size(6), Activation::Tanh)
+                        .convolutional(convolution),
+                    LayerTopology::with_activation(1, Activation::Identity),
+                ],
+                &mut rng,
+            );
+
+            assert_gradients_match_finite_differences(
+                &network,
+                &[0.1, 0.9, -0.4, 0.3, 0.0, -0.7],
+                &[0.5],
+            );
+        }
+    }
+
+    #[test]
+    fn sgd_reduces_loss() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = network(&mut rng);
+        let mut trainer = Trainer::new(Loss::MeanSquaredError, Optimizer::sgd(0.1), 4);
+
+        let losses = trainer.fit(&mut network, &samples(), 200, &mut rng);
+
+        assert!(losses[199] < losses[0] / 10.0);
+        assert!(losses[199] < 0.01);
+    }
+
+    #[test]
+    fn adam_reduces_loss() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = network(&mut rng);
+        let mut trainer = Trainer::new(Loss::MeanSquaredError, Optimizer::adam(0.01), 4);
+
+        let losses = trainer.fit(&mut network, &samples(), 200, &mut rng);
+
+        assert!(losses[199] < losses[0] / 10.0);
+        assert!(losses[199] < 0.01);
+    }
+
+    #[test]
+    fn trained_network_is_exportable() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = network(&mut rng);
+        let before = network.weights();
+
+        Trainer::new(Loss::MeanSquaredError, Optimizer::adam(0.01), 4).fit(
+            &mut network,
+            &samples(),
+            10,
+            &mut rng,
+        );
+
+        let after = network.weights();
+        assert_eq!(after.len(), before.len());
+        assert_ne!(after, before);
+
+        let restored = Network::from_weights(&network.topology(), after);
+
+        approx::assert_relative_eq!(
+            restored.propagate(vec![0.2, -0.1]).as_slice(),
+            network.propagate(vec![0.2, -0.1]).as_slice()
+        );
+    }
+}
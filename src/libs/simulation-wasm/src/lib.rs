@@ -19,6 +19,9 @@ impl Simulation {
         Self { rng, sim }
     }
 
+    // `from_serde()` is deprecated in newer wasm-bindgen releases, but
+    // it's still the simplest way of handing our `World` over to JS
+    #[allow(deprecated)]
     pub fn world(&self) -> JsValue {
         let world = World::from(self.sim.world());
         JsValue::from_serde(&world).unwrap()
@@ -44,6 +44,20 @@ impl Animal {
         self.rotation
     }
 
+    pub fn brain(&self) -> &Brain {
+        &self.brain
+    }
+
+    /// Shows what this animal's brain makes of the world right now
+    /// (see [`Brain::trace()`]).
+    pub fn trace_brain(&self, world: &World) -> nn::Trace {
+        let vision = self
+            .eye
+            .process_vision(self.position, self.rotation, &world.foods);
+
+        self.brain.trace(&vision)
+    }
+
     fn new(eye: Eye, brain: Brain, rng: &mut dyn RngCore) -> Self {
         Self {
             position: rng.gen(),
@@ -3,13 +3,31 @@ use crate::*;
 #[derive(Debug)]
 pub struct Brain {
     pub(crate) nn: nn::Network,
+    /// What the brain remembers from the previous step
+    pub(crate) state: nn::State,
 }
 
 impl Brain {
     pub fn random(rng: &mut dyn RngCore, eye: &Eye) -> Self {
-        Self {
-            nn: nn::Network::random(&Self::topology(eye), rng),
-        }
+        Self::random_with(rng, eye, nn::Initializer::default())
+    }
+
+    pub fn random_with(rng: &mut dyn RngCore, eye: &Eye, initializer: nn::Initializer) -> Self {
+        Self::new(nn::Network::random_with(
+            &Self::topology(eye),
+            initializer,
+            rng,
+        ))
+    }
+
+    pub fn network(&self) -> &nn::Network {
+        &self.nn
+    }
+
+    /// Shows what each layer computes for given vision, as if that was
+    /// the brain's next step - but without affecting what it remembers.
+    pub fn trace(&self, vision: &[f32]) -> nn::Trace {
+        self.nn.trace_recurrent(vision, &mut self.state.clone())
     }
 
     pub(crate) fn as_chromosome(&self) -> ga::Chromosome {
@@ -17,20 +35,24 @@ impl Brain {
     }
 
     pub(crate) fn from_chromosome(chromosome: ga::Chromosome, eye: &Eye) -> Self {
-        Self {
-            nn: nn::Network::from_weights(&Self::topology(eye), chromosome),
-        }
+        Self::new(nn::Network::from_weights(&Self::topology(eye), chromosome))
+    }
+
+    fn new(nn: nn::Network) -> Self {
+        let state = nn.state();
+
+        Self { nn, state }
     }
 
     fn topology(eye: &Eye) -> [nn::LayerTopology; 3] {
         [
-            nn::LayerTopology {
-                neurons: eye.cells(),
-            },
-            nn::LayerTopology {
-                neurons: 2 * eye.cells(),
-            },
-            nn::LayerTopology { neurons: 2 },
+            nn::LayerTopology::new(eye.cells()),
+            // Recurrent, so that food doesn't get forgotten the moment
+            // it leaves the eye's field of view
+            nn::LayerTopology::new(2 * eye.cells()).recurrent(),
+            // Tanh allows the brain to slow down and turn both ways,
+            // which ReLU (never going below zero) wouldn't allow
+            nn::LayerTopology::with_activation(2, nn::Activation::Tanh),
         ]
     }
 }
@@ -36,7 +36,7 @@ const SPEED_MAX: f32 = 0.005;
 /// yells "stop flying!", a SPEED_ACCEL of:
 ///
 /// - 0.1 = makes it take 5 steps ("5 seconds") for the bird to actually
-///         slow down to SPEED_MIN,
+///   slow down to SPEED_MIN,
 ///
 /// - 0.5 = makes it take 1 step for the bird to slow down to SPEED_MIN.
 ///
@@ -73,14 +73,23 @@ pub struct Simulation {
         ga::GaussianMutation,
     >,
     age: usize,
+    /// Brains of all the birds, so that the whole flock thinks at once;
+    /// rebuilt each generation
+    brains: nn::NetworkBatch,
+    /// What `brains` remember from the previous step
+    states: nn::BatchState,
+    /// Shared by all the brains, so that thinking doesn't allocate
+    scratch: nn::Scratch,
+    /// Visions of all the birds, one after another
+    vision: Vec<f32>,
 }
 
 impl Simulation {
     pub fn random(rng: &mut dyn RngCore) -> Self {
         let world = World::random(rng);
         let ga = ga::GeneticAlgorithm::new(
-            ga::RouletteWheelSelection::default(),
-            ga::UniformCrossover::default(),
+            ga::RouletteWheelSelection::new(),
+            ga::UniformCrossover::new(),
             ga::GaussianMutation::new(0.01, 0.3),
             // ---------------------- ^--^ -^-^
             // | Chosen with a bit of experimentation.
@@ -92,7 +101,17 @@ impl Simulation {
             // | enough solutions to be discarded.
             // ---
         );
-        Self { world, ga, age: 0 }
+        let (brains, states) = Self::brains(&world);
+
+        Self {
+            world,
+            ga,
+            age: 0,
+            brains,
+            states,
+            scratch: nn::Scratch::default(),
+            vision: Vec::new(),
+        }
     }
 
     pub fn world(&self) -> &World {
@@ -144,12 +163,30 @@ impl Simulation {
     }
 
     fn process_brains(&mut self) {
-        for animal in &mut self.world.animals {
-            let vision =
-                animal
-                    .eye
-                    .process_vision(animal.position, animal.rotation, &self.world.foods);
-            let response = animal.brain.nn.propagate(vision);
+        self.vision.clear();
+
+        for animal in &self.world.animals {
+            self.vision.extend(animal.eye.process_vision(
+                animal.position,
+                animal.rotation,
+                &self.world.foods,
+            ));
+        }
+
+        let responses =
+            self.brains
+                .propagate_recurrent(&self.vision, &mut self.states, &mut self.scratch);
+
+        for (idx, (animal, response)) in self
+            .world
+            .animals
+            .iter_mut()
+            .zip(responses.chunks(self.brains.output_size()))
+            .enumerate()
+        {
+            // Keeps each brain's own memory up to date, so that e.g.
+            // `Brain::trace()` sees what the bird actually remembers
+            self.states.copy_into(idx, &mut animal.brain.state);
 
             // ---
             // | Limits number to given range.
@@ -201,6 +238,8 @@ impl Simulation {
             .map(|individual| individual.into_animal(rng))
             .collect();
 
+        (self.brains, self.states) = Self::brains(&self.world);
+
         // Step 4: Restart foods
         //
         // (this is not strictly necessary, but it allows to easily spot
@@ -211,4 +250,11 @@ impl Simulation {
 
         stats
     }
+
+    fn brains(world: &World) -> (nn::NetworkBatch, nn::BatchState) {
+        let brains = nn::NetworkBatch::new(world.animals.iter().map(|animal| &animal.brain.nn));
+        let states = brains.state_from(world.animals.iter().map(|animal| &animal.brain.state));
+
+        (brains, states)
+    }
 }